@@ -17,3 +17,6 @@ cargo -V
 
 # Build and test main crate
 cargo build --locked --all-features
+
+# The unit tests run against the in-memory host, which is left out of the ABI export build
+cargo test --locked --lib
@@ -7,5 +7,9 @@ source ./ci/change-dir.sh
 # Call the function with parameters
 change_directory "$@"
 
+# The crates share the workspace target directory, but cargo stylus looks for
+# the wasm file in the target directory of the crate being checked
+export CARGO_TARGET_DIR="$PWD/target"
+
 # Stylus check
 cargo stylus check
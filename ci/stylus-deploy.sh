@@ -15,12 +15,23 @@ if [ -z "${PRIVATE_KEY:-}" ]; then
     fi
 fi
 
+# The initializer is baked into the contracts at build time
+if [ -z "${INITIALIZER_ADDRESS:-}" ]; then
+    echo "INITIALIZER_ADDRESS is not set, the contracts could not be initialized"
+    exit 1
+fi
+export INITIALIZER_ADDRESS
+
 # Source the function from the change_dir.sh script
 source ./ci/change-dir.sh
 
 # Call the function with parameters
 change_directory "$@"
 
+# The crates share the workspace target directory, but cargo stylus looks for
+# the wasm file in the target directory of the crate being checked
+export CARGO_TARGET_DIR="$PWD/target"
+
 # Deploy the contract
 cargo stylus deploy --private-key $PRIVATE_KEY
 
@@ -1,20 +1,54 @@
-#![cfg_attr(not(feature = "export-abi"), no_std, no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(feature = "export-abi"), no_std)]
 
 extern crate alloc;
-mod initialization;
+mod multisig;
+mod recovery;
+mod registry;
+mod roles;
 
-use initialization::{Initialization, InitializationError};
+use alloc::vec::Vec;
+use multisig::Multisig;
+use recovery::Recovery;
+use registry::Registry;
+use roles::Roles;
+use seabrick_common::{
+    erc165::Erc165,
+    initialization::{Initialization, InitializationError},
+    ownable::ERC173_INTERFACE_ID,
+};
 use stylus_sdk::{
-    alloy_primitives::Address,
+    abi::Bytes,
+    alloy_primitives::{fixed_bytes, Address, FixedBytes, U256, U64},
     alloy_sol_types::sol,
-    evm, msg,
+    block,
+    call::{call, Call},
+    contract, evm, msg,
     prelude::{entrypoint, public, sol_storage, SolidityError},
 };
 
+/// keccak256("RENOUNCE_OWNERSHIP"). Must be given to [`Ownership::renounce_ownership`]
+/// to confirm that the ownership is given up on purpose.
+pub const RENOUNCE_CONFIRMATION: FixedBytes<32> =
+    fixed_bytes!("b4ab8fce6526a73392b31bff605d1d3785954601c0b5d1ed97d83d1c8bbc05a2");
+
 sol! {
     /// Emitted when ownership gets transferred between accounts.
     #[allow(missing_docs)]
     event OwnershipTransferred(address indexed previous_owner, address indexed new_owner);
+
+    /// Emitted when an ownership transfer is started and waits to be accepted.
+    /// An `expiry` of zero means the transfer does not expire.
+    #[allow(missing_docs)]
+    event OwnershipTransferStarted(address indexed previous_owner, address indexed new_owner, uint64 expiry);
+
+    /// Emitted when a pending ownership transfer is cancelled by the owner.
+    #[allow(missing_docs)]
+    event OwnershipTransferCancelled(address indexed owner, address indexed pending_owner);
+
+    /// Emitted when the owner executes a batch of calls through this contract.
+    #[allow(missing_docs)]
+    event BatchExecuted(address indexed executor, uint256 calls);
 }
 
 sol! {
@@ -30,6 +64,36 @@ sol! {
     #[derive(Debug)]
     #[allow(missing_docs)]
     error OwnershipInvalidOwner(address owner);
+    /// The pending ownership transfer can no longer be accepted.
+    ///
+    /// * `expiry` - Timestamp after which the transfer expired.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error OwnershipTransferExpired(uint64 expiry);
+    /// The expiry given for an ownership transfer is already in the past.
+    ///
+    /// * `expiry` - The invalid expiry timestamp.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error OwnershipInvalidExpiry(uint64 expiry);
+    /// The confirmation given to renounce the ownership is not valid.
+    ///
+    /// * `confirmation` - The invalid confirmation.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error OwnershipInvalidConfirmation(bytes32 confirmation);
+    /// The targets, calldatas and values of a batch don't have the same length.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error OwnershipBatchLengthMismatch(uint256 targets, uint256 calldatas, uint256 values);
+    /// The values of a batch don't add up to the value sent with it.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error OwnershipBatchValueMismatch(uint256 values, uint256 value);
+    /// The ownership was renounced, so it can't be recovered.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error OwnershipRenounced();
 }
 
 #[derive(SolidityError, Debug)]
@@ -38,6 +102,18 @@ pub enum OwnershipError {
     UnauthorizedAccount(OwnershipUnauthorizedAccount),
     /// The owner is not a valid owner account. (eg. `Address::ZERO`)
     InvalidOwner(OwnershipInvalidOwner),
+    /// The pending ownership transfer expired before being accepted.
+    TransferExpired(OwnershipTransferExpired),
+    /// The expiry for the ownership transfer is already in the past.
+    InvalidExpiry(OwnershipInvalidExpiry),
+    /// The confirmation to renounce the ownership is not valid.
+    InvalidConfirmation(OwnershipInvalidConfirmation),
+    /// The batch arguments don't have the same length.
+    BatchLengthMismatch(OwnershipBatchLengthMismatch),
+    /// The batch values don't add up to the value sent.
+    BatchValueMismatch(OwnershipBatchValueMismatch),
+    /// The ownership was renounced.
+    OwnershipRenounced(OwnershipRenounced),
 }
 
 sol_storage! {
@@ -46,11 +122,31 @@ sol_storage! {
         address _owner;
         #[borrow]
         Initialization init;
+        /// Account that has to accept the ownership before it's transferred
+        address _pending_owner;
+        /// Timestamp after which the pending transfer can't be accepted. Zero if it never expires
+        uint64 _pending_owner_expiry;
+        /// Signers and proposals used when the contract owns itself
+        #[borrow]
+        Multisig multisig;
+        /// Roles granted by the owner
+        #[borrow]
+        Roles roles;
+        /// Named addresses of the managed contracts
+        #[borrow]
+        Registry registry;
+        /// Guardians able to recover the ownership if the owner key is lost
+        #[borrow]
+        Recovery recovery;
+        /// Interfaces reported through ERC-165
+        #[borrow]
+        Erc165 erc165;
     }
 }
 
 impl Ownership {
-    /// Checks if the [`msg::sender`] is set as the owner.
+    /// Checks if the [`msg::sender`] is set as the owner. Calls made by this
+    /// contract to itself (eg. inside [`Self::execute_batch`]) are allowed too.
     ///
     /// # Errors
     ///
@@ -58,7 +154,8 @@ impl Ownership {
     /// [`Error::UnauthorizedAccount`] is returned.
     pub fn only_owner(&self) -> Result<(), OwnershipError> {
         let account = msg::sender();
-        if self.owner() != account {
+        let owner = self.owner();
+        if owner.is_zero() || (owner != account && contract::address() != account) {
             return Err(OwnershipError::UnauthorizedAccount(
                 OwnershipUnauthorizedAccount { account },
             ));
@@ -68,6 +165,7 @@ impl Ownership {
     }
 
     /// Transfers ownership of the contract to a new account (`new_owner`).
+    /// Any recovery in progress is cancelled.
     /// Internal function without access restriction.
     ///
     /// # Arguments
@@ -75,6 +173,10 @@ impl Ownership {
     /// * `&mut self` - Write access to the contract's state.
     /// * `new_owner` - Account that's gonna be the next owner.
     pub fn _transfer_ownership(&mut self, new_owner: Address) {
+        self._pending_owner.set(Address::ZERO);
+        self._pending_owner_expiry.set(U64::ZERO);
+        self.recovery._cancel();
+
         let previous_owner = self._owner.get();
         self._owner.set(new_owner);
         evm::log(OwnershipTransferred {
@@ -82,9 +184,49 @@ impl Ownership {
             new_owner,
         });
     }
+
+    /// Starts the transfer of the ownership to `new_owner`, which has to accept it
+    /// before `expiry` (if it's not zero).
+    /// Internal function without access restriction.
+    ///
+    /// # Errors
+    ///
+    /// If `new_owner` is the zero address, then the error
+    /// [`OwnershipInvalidOwner`] is returned.
+    /// If `expiry` is not zero and already passed, then the error
+    /// [`OwnershipInvalidExpiry`] is returned.
+    pub fn _start_ownership_transfer(
+        &mut self,
+        new_owner: Address,
+        expiry: u64,
+    ) -> Result<(), OwnershipError> {
+        if new_owner == Address::ZERO {
+            return Err(OwnershipError::InvalidOwner(OwnershipInvalidOwner {
+                owner: Address::ZERO,
+            }));
+        }
+
+        if expiry != 0 && expiry <= block::timestamp() {
+            return Err(OwnershipError::InvalidExpiry(OwnershipInvalidExpiry {
+                expiry,
+            }));
+        }
+
+        self._pending_owner.set(new_owner);
+        self._pending_owner_expiry.set(U64::from(expiry));
+
+        evm::log(OwnershipTransferStarted {
+            previous_owner: self._owner.get(),
+            new_owner,
+            expiry,
+        });
+
+        Ok(())
+    }
 }
 
 #[public]
+#[inherit(Initialization, Multisig, Roles, Registry, Recovery, Erc165)]
 impl Ownership {
     pub fn initialization(&mut self, owner: Address) -> Result<(), InitializationError> {
         // Check if already init. Revert if already init
@@ -93,8 +235,11 @@ impl Ownership {
         // Set contract owner
         self._transfer_ownership(owner);
 
+        // Report ERC-173, so the contracts accept this one as their ownership contract
+        self.erc165._register_interface(ERC173_INTERFACE_ID);
+
         // Change contract state to already initialized
-        self.init._set_init(true);
+        self.init._set_initialized(1);
 
         Ok(())
     }
@@ -104,8 +249,20 @@ impl Ownership {
         self._owner.get()
     }
 
-    /// Transfers ownership of the contract to a new account (`new_owner`). Can
-    /// only be called by the current owner.
+    /// Returns the address of the account that has to accept the ownership.
+    pub fn pending_owner(&self) -> Address {
+        self._pending_owner.get()
+    }
+
+    /// Returns the timestamp after which the pending ownership transfer can't be
+    /// accepted, or zero if it does not expire.
+    pub fn pending_owner_expiry(&self) -> u64 {
+        self._pending_owner_expiry.get().to()
+    }
+
+    /// Starts the transfer of the ownership of the contract to a new account
+    /// (`new_owner`). The transfer is completed when `new_owner` calls
+    /// [`Self::accept_ownership`]. Can only be called by the current owner.
     ///
     /// # Arguments
     ///
@@ -119,14 +276,915 @@ impl Ownership {
     pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), OwnershipError> {
         self.only_owner()?;
 
-        if new_owner == Address::ZERO {
-            return Err(OwnershipError::InvalidOwner(OwnershipInvalidOwner {
-                owner: Address::ZERO,
+        self._start_ownership_transfer(new_owner, 0)
+    }
+
+    /// Same as [`Self::transfer_ownership`], but the transfer can only be accepted
+    /// until the `expiry` timestamp.
+    ///
+    /// # Errors
+    ///
+    /// If `new_owner` is the zero address, then the error
+    /// [`OwnershipInvalidOwner`] is returned.
+    /// If `expiry` already passed, then the error [`OwnershipInvalidExpiry`] is returned.
+    pub fn transfer_ownership_with_expiry(
+        &mut self,
+        new_owner: Address,
+        expiry: u64,
+    ) -> Result<(), OwnershipError> {
+        self.only_owner()?;
+
+        if expiry == 0 {
+            return Err(OwnershipError::InvalidExpiry(OwnershipInvalidExpiry {
+                expiry,
+            }));
+        }
+
+        self._start_ownership_transfer(new_owner, expiry)
+    }
+
+    /// Completes the pending ownership transfer. Can only be called by the
+    /// pending owner.
+    ///
+    /// # Errors
+    ///
+    /// If called by any account other than the pending owner, then the error
+    /// [`OwnershipUnauthorizedAccount`] is returned.
+    /// If the transfer expired, then the error [`OwnershipTransferExpired`] is returned.
+    pub fn accept_ownership(&mut self) -> Result<(), OwnershipError> {
+        let account = msg::sender();
+        if self._pending_owner.get() != account {
+            return Err(OwnershipError::UnauthorizedAccount(
+                OwnershipUnauthorizedAccount { account },
+            ));
+        }
+
+        let expiry = self.pending_owner_expiry();
+        if expiry != 0 && block::timestamp() > expiry {
+            return Err(OwnershipError::TransferExpired(OwnershipTransferExpired {
+                expiry,
             }));
         }
 
+        self._transfer_ownership(account);
+
+        Ok(())
+    }
+
+    /// Leaves the contract without owner, so no owner action can be performed
+    /// anymore here or in the dependent contracts. Can only be called by the
+    /// current owner, with [`RENOUNCE_CONFIRMATION`] as `confirmation`.
+    ///
+    /// # Errors
+    ///
+    /// If `confirmation` is not [`RENOUNCE_CONFIRMATION`], then the error
+    /// [`OwnershipInvalidConfirmation`] is returned.
+    pub fn renounce_ownership(
+        &mut self,
+        confirmation: FixedBytes<32>,
+    ) -> Result<(), OwnershipError> {
+        self.only_owner()?;
+
+        if confirmation != RENOUNCE_CONFIRMATION {
+            return Err(OwnershipError::InvalidConfirmation(
+                OwnershipInvalidConfirmation { confirmation },
+            ));
+        }
+
+        self._transfer_ownership(Address::ZERO);
+
+        Ok(())
+    }
+
+    /// Returns the confirmation needed by [`Self::renounce_ownership`].
+    #[selector(name = "RENOUNCE_CONFIRMATION")]
+    pub fn renounce_confirmation(&self) -> FixedBytes<32> {
+        RENOUNCE_CONFIRMATION
+    }
+
+    /// Cancels the pending ownership transfer. Can only be called by the current owner.
+    pub fn cancel_ownership_transfer(&mut self) -> Result<(), OwnershipError> {
+        self.only_owner()?;
+
+        let pending_owner = self._pending_owner.get();
+        self._pending_owner.set(Address::ZERO);
+        self._pending_owner_expiry.set(U64::ZERO);
+
+        evm::log(OwnershipTransferCancelled {
+            owner: self._owner.get(),
+            pending_owner,
+        });
+
+        Ok(())
+    }
+
+    /// Turns on the multisig mode: sets the `signers` and `threshold` and makes
+    /// this contract its own owner. From then on, every owner action (in this
+    /// and the dependent contracts) has to be proposed, confirmed and executed
+    /// through the multisig. Can only be called by the current owner. The
+    /// proposals left from an earlier multisig mode can't be executed anymore.
+    ///
+    /// The multisig mode can be left by executing a [`Self::transfer_ownership`]
+    /// proposal.
+    pub fn enable_multisig(
+        &mut self,
+        signers: Vec<Address>,
+        threshold: U256,
+    ) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+
+        self.multisig._set_signers(signers, threshold)?;
+        self._transfer_ownership(contract::address());
+
+        Ok(())
+    }
+
+    /// Whether the contract is owned by its own multisig.
+    pub fn is_multisig(&self) -> bool {
+        self._owner.get() == contract::address()
+    }
+
+    /// Adds a multisig signer, dropping the pending proposals. Can only be called
+    /// by the current owner.
+    pub fn add_signer(&mut self, signer: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.multisig._add_signer(signer)?;
+
+        Ok(())
+    }
+
+    /// Removes a multisig signer, dropping the pending proposals. Can only be
+    /// called by the current owner.
+    pub fn remove_signer(&mut self, signer: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.multisig._remove_signer(signer)?;
+
+        Ok(())
+    }
+
+    /// Changes the multisig threshold. Can only be called by the current owner.
+    pub fn change_threshold(&mut self, threshold: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.multisig._set_threshold(threshold)?;
+
+        Ok(())
+    }
+
+    /// Executes the proposal `id` once it has enough confirmations. Can only be
+    /// called by a signer, while the multisig mode is on. The call is made by
+    /// this contract, so it's seen as the owner by the dependent contracts.
+    ///
+    /// # Errors
+    ///
+    /// If the multisig mode is off, then the error [`OwnershipUnauthorizedAccount`]
+    /// is returned, since the signers left don't own the contracts anymore.
+    /// If the target call reverts, the revert data is returned as is.
+    #[payable]
+    pub fn execute(&mut self, id: U256) -> Result<Bytes, Vec<u8>> {
+        self.multisig.only_signer()?;
+        if !self.is_multisig() {
+            return Err(
+                OwnershipError::UnauthorizedAccount(OwnershipUnauthorizedAccount {
+                    account: msg::sender(),
+                })
+                .into(),
+            );
+        }
+
+        let (target, value, data) = self.multisig._prepare_execution(id)?;
+        let result = call(Call::new_in(self).value(value), target, &data)?;
+
+        Ok(result.into())
+    }
+
+    /// Calls every `targets[i]` with `calldatas[i]` and `values[i]` from this
+    /// contract, which the dependent contracts see as the owner. It's atomic:
+    /// if any call reverts, the whole batch is reverted with its revert data.
+    /// Can only be called by the current owner. The `values` must add up to the
+    /// value sent, so nothing is left behind in this contract.
+    ///
+    /// Returns the output of each call.
+    ///
+    /// # Errors
+    ///
+    /// If the arguments don't have the same length, then the error
+    /// [`OwnershipBatchLengthMismatch`] is returned. If the `values` don't add up
+    /// to the value sent, then the error [`OwnershipBatchValueMismatch`] is
+    /// returned.
+    #[payable]
+    pub fn execute_batch(
+        &mut self,
+        targets: Vec<Address>,
+        calldatas: Vec<Bytes>,
+        values: Vec<U256>,
+    ) -> Result<Vec<Bytes>, Vec<u8>> {
+        self.only_owner()?;
+
+        if targets.len() != calldatas.len() || targets.len() != values.len() {
+            return Err(
+                OwnershipError::BatchLengthMismatch(OwnershipBatchLengthMismatch {
+                    targets: U256::from(targets.len()),
+                    calldatas: U256::from(calldatas.len()),
+                    values: U256::from(values.len()),
+                })
+                .into(),
+            );
+        }
+
+        let total = values
+            .iter()
+            .fold(U256::ZERO, |total, value| total.saturating_add(*value));
+        if total != msg::value() {
+            return Err(
+                OwnershipError::BatchValueMismatch(OwnershipBatchValueMismatch {
+                    values: total,
+                    value: msg::value(),
+                })
+                .into(),
+            );
+        }
+
+        let mut results = Vec::with_capacity(targets.len());
+        for ((target, data), value) in targets.iter().zip(calldatas).zip(values) {
+            let result = call(Call::new_in(self).value(value), *target, &data)?;
+            results.push(result.into());
+        }
+
+        evm::log(BatchExecuted {
+            executor: msg::sender(),
+            calls: U256::from(targets.len()),
+        });
+
+        Ok(results)
+    }
+
+    /// Grants `role` to `account`. Can only be called by the current owner.
+    pub fn grant_role(
+        &mut self,
+        role: FixedBytes<32>,
+        account: Address,
+    ) -> Result<(), OwnershipError> {
+        self.only_owner()?;
+        self.roles._grant_role(role, account);
+
+        Ok(())
+    }
+
+    /// Revokes `role` from `account`. Can only be called by the current owner.
+    pub fn revoke_role(
+        &mut self,
+        role: FixedBytes<32>,
+        account: Address,
+    ) -> Result<(), OwnershipError> {
+        self.only_owner()?;
+        self.roles._revoke_role(role, account);
+
+        Ok(())
+    }
+
+    /// Registers `addr` as the contract `name` (eg. [`registry::MARKET`]),
+    /// replacing the previous address if any. Can only be called by the current owner.
+    pub fn set_contract(&mut self, name: FixedBytes<32>, addr: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.registry._set_contract(name, addr)?;
+
+        Ok(())
+    }
+
+    /// Removes the contract `name` from the registry. Can only be called by the
+    /// current owner.
+    pub fn remove_contract(&mut self, name: FixedBytes<32>) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.registry._remove_contract(name)?;
+
+        Ok(())
+    }
+
+    /// Sets the recovery `guardians`, the supports needed (`threshold`) and the
+    /// `delay` the owner has to veto a recovery, cancelling any recovery in
+    /// progress. Can only be called by the current owner.
+    pub fn set_guardians(
+        &mut self,
+        guardians: Vec<Address>,
+        threshold: U256,
+        delay: u64,
+    ) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.recovery._set_guardians(guardians, threshold, delay)?;
+
+        Ok(())
+    }
+
+    /// Cancels the scheduled recovery. Can only be called by the current owner.
+    pub fn veto_recovery(&mut self) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.recovery._veto(self._owner.get())?;
+
+        Ok(())
+    }
+
+    /// Transfers the ownership to the owner proposed by the guardians, once it
+    /// has enough support and the veto delay has passed. Can be called by anyone.
+    ///
+    /// # Errors
+    ///
+    /// If the ownership was renounced, then the error [`OwnershipRenounced`]
+    /// is returned.
+    pub fn execute_recovery(&mut self) -> Result<(), Vec<u8>> {
+        let previous_owner = self._owner.get();
+        if previous_owner.is_zero() {
+            return Err(OwnershipError::OwnershipRenounced(OwnershipRenounced {}).into());
+        }
+
+        let new_owner = self.recovery._complete(previous_owner)?;
         self._transfer_ownership(new_owner);
 
         Ok(())
     }
 }
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use crate::{
+        recovery::{
+            RecoveryError, RecoveryInvalidDelay, RecoveryNotProposed, RecoveryNotReady,
+            MIN_RECOVERY_DELAY,
+        },
+        registry::{RegistryInvalidContract, RegistryUnknownContract, MARKET, SEABRICK_NFT, VAULT},
+        roles::{RoleGranted, RoleRevoked, PRICE_ADMIN, VAULT_ADMIN},
+    };
+    use alloc::vec;
+    use seabrick_common::{erc165::ERC165_INTERFACE_ID, testing};
+    use stylus_sdk::{
+        alloy_primitives::{address, U256},
+        alloy_sol_types::{SolError, SolEvent},
+        storage::StorageType,
+    };
+
+    const ALICE: Address = address!("000000000000000000000000000000000000a11c");
+    const BOB: Address = address!("0000000000000000000000000000000000000b0b");
+    const CAROL: Address = address!("00000000000000000000000000000000000ca201");
+
+    fn ownership(owner: Address) -> Ownership {
+        testing::reset();
+        let mut ownership = unsafe { Ownership::new(U256::ZERO, 0) };
+        ownership._transfer_ownership(owner);
+        ownership
+    }
+
+    #[test]
+    fn transfer_waits_for_the_new_owner() {
+        let mut ownership = ownership(testing::SENDER);
+
+        assert!(ownership.transfer_ownership(ALICE).is_ok());
+
+        assert_eq!(ownership.owner(), testing::SENDER);
+        assert_eq!(ownership.pending_owner(), ALICE);
+        assert_eq!(ownership.pending_owner_expiry(), 0);
+
+        // Only Alice can accept it
+        assert!(matches!(
+            ownership.accept_ownership(),
+            Err(OwnershipError::UnauthorizedAccount(_))
+        ));
+    }
+
+    #[test]
+    fn pending_owner_accepts_the_ownership() {
+        let mut ownership = ownership(ALICE);
+        assert!(ownership
+            ._start_ownership_transfer(testing::SENDER, 0)
+            .is_ok());
+
+        assert!(ownership.accept_ownership().is_ok());
+
+        assert_eq!(ownership.owner(), testing::SENDER);
+        assert_eq!(ownership.pending_owner(), Address::ZERO);
+    }
+
+    #[test]
+    fn only_the_owner_starts_or_cancels_a_transfer() {
+        let mut ownership = ownership(ALICE);
+
+        assert!(matches!(
+            ownership.transfer_ownership(testing::SENDER),
+            Err(OwnershipError::UnauthorizedAccount(_))
+        ));
+        assert!(matches!(
+            ownership.transfer_ownership_with_expiry(testing::SENDER, testing::TIMESTAMP + 1),
+            Err(OwnershipError::UnauthorizedAccount(_))
+        ));
+        assert!(matches!(
+            ownership.cancel_ownership_transfer(),
+            Err(OwnershipError::UnauthorizedAccount(_))
+        ));
+        assert_eq!(ownership.pending_owner(), Address::ZERO);
+    }
+
+    #[test]
+    fn owner_cancels_a_pending_transfer() {
+        let mut ownership = ownership(testing::SENDER);
+        assert!(ownership
+            .transfer_ownership_with_expiry(ALICE, testing::TIMESTAMP + 1)
+            .is_ok());
+
+        assert!(ownership.cancel_ownership_transfer().is_ok());
+
+        assert_eq!(ownership.pending_owner(), Address::ZERO);
+        assert_eq!(ownership.pending_owner_expiry(), 0);
+        assert_eq!(ownership.owner(), testing::SENDER);
+    }
+
+    #[test]
+    fn transfer_to_the_zero_address_is_rejected() {
+        let mut ownership = ownership(testing::SENDER);
+
+        assert!(matches!(
+            ownership.transfer_ownership(Address::ZERO),
+            Err(OwnershipError::InvalidOwner(_))
+        ));
+    }
+
+    #[test]
+    fn expiring_transfer_is_accepted_until_its_expiry() {
+        let mut ownership = ownership(ALICE);
+        assert!(ownership
+            ._start_ownership_transfer(testing::SENDER, testing::TIMESTAMP)
+            .is_err());
+        assert!(ownership
+            ._start_ownership_transfer(testing::SENDER, testing::TIMESTAMP + 1)
+            .is_ok());
+
+        assert!(ownership.accept_ownership().is_ok());
+        assert_eq!(ownership.owner(), testing::SENDER);
+    }
+
+    #[test]
+    fn expired_transfer_is_rejected() {
+        let mut ownership = ownership(ALICE);
+        assert!(ownership
+            ._start_ownership_transfer(testing::SENDER, testing::TIMESTAMP + 1)
+            .is_ok());
+        // As if the expiry passed
+        ownership
+            ._pending_owner_expiry
+            .set(U64::from(testing::TIMESTAMP - 1));
+
+        assert!(matches!(
+            ownership.accept_ownership(),
+            Err(OwnershipError::TransferExpired(_))
+        ));
+        assert_eq!(ownership.owner(), ALICE);
+    }
+
+    #[test]
+    fn expiry_must_be_in_the_future() {
+        let mut ownership = ownership(testing::SENDER);
+
+        for expiry in [0, testing::TIMESTAMP] {
+            assert!(matches!(
+                ownership.transfer_ownership_with_expiry(ALICE, expiry),
+                Err(OwnershipError::InvalidExpiry(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn owner_grants_and_revokes_roles() {
+        let mut ownership = ownership(testing::SENDER);
+
+        assert!(ownership.grant_role(PRICE_ADMIN, ALICE).is_ok());
+        assert!(ownership.roles.has_role(PRICE_ADMIN, ALICE));
+        assert!(!ownership.roles.has_role(VAULT_ADMIN, ALICE));
+
+        // Granting twice is a no-op
+        assert!(ownership.grant_role(PRICE_ADMIN, ALICE).is_ok());
+
+        assert!(ownership.revoke_role(PRICE_ADMIN, ALICE).is_ok());
+        assert!(!ownership.roles.has_role(PRICE_ADMIN, ALICE));
+
+        let events: Vec<[u8; 32]> = testing::take_logs()
+            .into_iter()
+            .map(|log| log.topics[0])
+            .filter(|topic| *topic != OwnershipTransferred::SIGNATURE_HASH.0)
+            .collect();
+        assert_eq!(
+            events,
+            [RoleGranted::SIGNATURE_HASH.0, RoleRevoked::SIGNATURE_HASH.0]
+        );
+    }
+
+    #[test]
+    fn only_the_owner_manages_roles() {
+        let mut ownership = ownership(ALICE);
+
+        assert!(matches!(
+            ownership.grant_role(PRICE_ADMIN, testing::SENDER),
+            Err(OwnershipError::UnauthorizedAccount(_))
+        ));
+        ownership.roles._grant_role(PRICE_ADMIN, testing::SENDER);
+        assert!(matches!(
+            ownership.revoke_role(PRICE_ADMIN, testing::SENDER),
+            Err(OwnershipError::UnauthorizedAccount(_))
+        ));
+        assert!(ownership.roles.has_role(PRICE_ADMIN, testing::SENDER));
+    }
+
+    #[test]
+    fn owner_renounces_with_the_confirmation() {
+        let mut ownership = ownership(testing::SENDER);
+
+        assert!(matches!(
+            ownership.renounce_ownership(FixedBytes::ZERO),
+            Err(OwnershipError::InvalidConfirmation(_))
+        ));
+        assert_eq!(ownership.owner(), testing::SENDER);
+
+        assert!(ownership.renounce_ownership(RENOUNCE_CONFIRMATION).is_ok());
+        assert_eq!(ownership.owner(), Address::ZERO);
+
+        // Nothing can be done as owner anymore
+        assert!(matches!(
+            ownership.transfer_ownership(testing::SENDER),
+            Err(OwnershipError::UnauthorizedAccount(_))
+        ));
+        assert!(matches!(
+            ownership.grant_role(PRICE_ADMIN, testing::SENDER),
+            Err(OwnershipError::UnauthorizedAccount(_))
+        ));
+    }
+
+    #[test]
+    fn only_the_owner_renounces() {
+        let mut ownership = ownership(ALICE);
+
+        assert!(matches!(
+            ownership.renounce_ownership(RENOUNCE_CONFIRMATION),
+            Err(OwnershipError::UnauthorizedAccount(_))
+        ));
+        assert_eq!(ownership.owner(), ALICE);
+    }
+
+    #[test]
+    fn owner_registers_and_enumerates_contracts() {
+        let mut ownership = ownership(testing::SENDER);
+        let other = FixedBytes::from([7; 32]);
+
+        assert!(ownership.set_contract(SEABRICK_NFT, ALICE).is_ok());
+        assert!(ownership.set_contract(MARKET, testing::CONTRACT).is_ok());
+        assert!(ownership.set_contract(other, ALICE).is_ok());
+        // Updating keeps its position
+        assert!(ownership
+            .set_contract(SEABRICK_NFT, testing::SENDER)
+            .is_ok());
+
+        assert_eq!(
+            ownership.registry.get_contract(SEABRICK_NFT),
+            testing::SENDER
+        );
+        assert!(ownership.registry.is_registered(MARKET));
+        assert!(!ownership.registry.is_registered(VAULT));
+        assert_eq!(
+            ownership.registry.contracts(),
+            (
+                vec![SEABRICK_NFT, MARKET, other],
+                vec![testing::SENDER, testing::CONTRACT, ALICE]
+            )
+        );
+
+        assert!(matches!(
+            ownership.set_contract(VAULT, Address::ZERO),
+            Err(err) if err.starts_with(&RegistryInvalidContract::SELECTOR)
+        ));
+    }
+
+    #[test]
+    fn owner_removes_contracts() {
+        let mut ownership = ownership(testing::SENDER);
+        for (name, addr) in [
+            (SEABRICK_NFT, ALICE),
+            (MARKET, ALICE),
+            (VAULT, testing::SENDER),
+        ] {
+            assert!(ownership.set_contract(name, addr).is_ok());
+        }
+
+        // The last name takes the place of the removed one
+        assert!(ownership.remove_contract(SEABRICK_NFT).is_ok());
+        assert_eq!(ownership.registry.contract_names(), vec![VAULT, MARKET]);
+        assert_eq!(ownership.registry.get_contract(SEABRICK_NFT), Address::ZERO);
+        assert!(!ownership.registry.is_registered(SEABRICK_NFT));
+
+        assert!(ownership.remove_contract(MARKET).is_ok());
+        assert!(ownership.remove_contract(VAULT).is_ok());
+        assert!(ownership.registry.contract_names().is_empty());
+
+        assert!(matches!(
+            ownership.remove_contract(VAULT),
+            Err(err) if err.starts_with(&RegistryUnknownContract::SELECTOR)
+        ));
+    }
+
+    #[test]
+    fn only_the_owner_manages_the_registry() {
+        let mut ownership = ownership(ALICE);
+        assert!(ownership.registry._set_contract(MARKET, ALICE).is_ok());
+
+        let unauthorized = |result: Result<(), Vec<u8>>| matches!(result, Err(err) if err.starts_with(&OwnershipUnauthorizedAccount::SELECTOR));
+        assert!(unauthorized(
+            ownership.set_contract(MARKET, testing::SENDER)
+        ));
+        assert!(unauthorized(ownership.remove_contract(MARKET)));
+        assert_eq!(ownership.registry.get_contract(MARKET), ALICE);
+    }
+
+    #[test]
+    fn owner_executes_a_batch_with_its_values() {
+        let mut ownership = ownership(testing::SENDER);
+        testing::on_call(|to, calldata| Ok([to.as_slice(), calldata].concat()));
+
+        let result = ownership.execute_batch(
+            vec![ALICE, testing::CONTRACT],
+            vec![b"first".to_vec().into(), b"second".to_vec().into()],
+            vec![U256::from(600), U256::from(400)],
+        );
+
+        let outputs: Vec<Vec<u8>> = result.unwrap().into_iter().map(|out| out.0).collect();
+        assert_eq!(
+            outputs,
+            [
+                [ALICE.as_slice(), b"first"].concat(),
+                [testing::CONTRACT.as_slice(), b"second"].concat()
+            ]
+        );
+        assert_eq!(
+            testing::take_calls(),
+            [
+                (ALICE, U256::from(600)),
+                (testing::CONTRACT, U256::from(400))
+            ]
+        );
+        let log = testing::take_logs().pop().unwrap();
+        assert_eq!(log.topics[0], BatchExecuted::SIGNATURE_HASH.0);
+    }
+
+    #[test]
+    fn batch_arguments_must_have_the_same_length() {
+        let mut ownership = ownership(testing::SENDER);
+        testing::on_call(|_, _| Ok(vec![]));
+
+        let result = ownership.execute_batch(
+            vec![ALICE, ALICE],
+            vec![Bytes::from(vec![]), Bytes::from(vec![])],
+            vec![U256::ZERO],
+        );
+
+        let mismatch = OwnershipError::BatchLengthMismatch(OwnershipBatchLengthMismatch {
+            targets: U256::from(2),
+            calldatas: U256::from(2),
+            values: U256::from(1),
+        });
+        assert_eq!(result, Err(mismatch.into()));
+        assert!(testing::take_calls().is_empty());
+    }
+
+    #[test]
+    fn batch_values_must_add_up_to_the_value_sent() {
+        let mut ownership = ownership(testing::SENDER);
+        testing::on_call(|_, _| Ok(vec![]));
+
+        let result = ownership.execute_batch(
+            vec![ALICE, ALICE],
+            vec![Bytes::from(vec![]), Bytes::from(vec![])],
+            vec![U256::from(1), U256::ZERO],
+        );
+
+        let mismatch = OwnershipError::BatchValueMismatch(OwnershipBatchValueMismatch {
+            values: U256::from(1),
+            value: testing::VALUE,
+        });
+        assert_eq!(result, Err(mismatch.into()));
+        assert!(testing::take_calls().is_empty());
+    }
+
+    #[test]
+    fn batch_reverts_with_the_failing_call() {
+        let mut ownership = ownership(testing::SENDER);
+        testing::on_call(|to, _| match to {
+            ALICE => Ok(vec![]),
+            _ => Err(b"reverted".to_vec()),
+        });
+
+        let result = ownership.execute_batch(
+            vec![ALICE, testing::CONTRACT],
+            vec![Bytes::from(vec![]), Bytes::from(vec![])],
+            vec![testing::VALUE, U256::ZERO],
+        );
+
+        assert_eq!(result, Err(b"reverted".to_vec()));
+    }
+
+    #[test]
+    fn only_the_owner_executes_a_batch() {
+        let mut ownership = ownership(ALICE);
+
+        let result = ownership.execute_batch(vec![], vec![], vec![]);
+
+        assert!(matches!(
+            result,
+            Err(err) if err.starts_with(&OwnershipUnauthorizedAccount::SELECTOR)
+        ));
+    }
+
+    /// An Ownership owned by the sender, with the sender, Alice and Bob as guardians
+    fn guarded(threshold: u64) -> Ownership {
+        let mut ownership = ownership(testing::SENDER);
+        let guardians = vec![testing::SENDER, ALICE, BOB];
+        assert!(ownership
+            .set_guardians(guardians, U256::from(threshold), MIN_RECOVERY_DELAY)
+            .is_ok());
+        ownership
+    }
+
+    /// Adds the support of `guardian` for `new_owner` to the current round, as if it
+    /// called in without reaching the threshold
+    fn support_from(ownership: &mut Ownership, guardian: Address, new_owner: Address) {
+        let round = ownership.recovery.pending_recovery().0;
+        ownership
+            .recovery
+            .supported_by
+            .setter(round)
+            .insert(guardian, new_owner);
+    }
+
+    /// Moves the ETA of the recovery to now, as if the veto delay passed
+    fn skip_veto_delay(ownership: &mut Ownership) {
+        ownership.recovery.eta.set(U64::from(testing::TIMESTAMP));
+    }
+
+    fn recovery_error(result: Result<(), Vec<u8>>, selector: [u8; 4]) -> bool {
+        matches!(result, Err(err) if err.starts_with(&selector))
+    }
+
+    #[test]
+    fn recovery_is_scheduled_once_the_threshold_is_reached() {
+        let mut ownership = guarded(3);
+        support_from(&mut ownership, ALICE, CAROL);
+
+        assert!(ownership.recovery.propose_recovery(CAROL).is_ok());
+        let (_, candidate, eta) = ownership.recovery.pending_recovery();
+        assert_eq!((candidate, eta), (Address::ZERO, 0));
+        assert_eq!(ownership.recovery.recovery_supports(CAROL), U256::from(2));
+        assert!(recovery_error(
+            ownership.execute_recovery(),
+            RecoveryNotProposed::SELECTOR
+        ));
+
+        // The sender moves its support away and back once Bob supports Carol too
+        support_from(&mut ownership, BOB, CAROL);
+        assert!(ownership.recovery.propose_recovery(ALICE).is_ok());
+        assert!(ownership.recovery.propose_recovery(CAROL).is_ok());
+        skip_veto_delay(&mut ownership);
+        assert!(ownership.execute_recovery().is_ok());
+        assert_eq!(ownership.owner(), CAROL);
+    }
+
+    #[test]
+    fn other_proposals_do_not_block_the_recovery() {
+        let mut ownership = guarded(2);
+        // Bob proposes someone else first
+        support_from(&mut ownership, BOB, BOB);
+        support_from(&mut ownership, ALICE, CAROL);
+
+        assert!(ownership.recovery.propose_recovery(CAROL).is_ok());
+
+        let (_, candidate, eta) = ownership.recovery.pending_recovery();
+        assert_eq!(candidate, CAROL);
+        assert_eq!(eta, testing::TIMESTAMP + MIN_RECOVERY_DELAY);
+        assert_eq!(ownership.recovery.recovery_supports(BOB), U256::from(1));
+    }
+
+    #[test]
+    fn guardians_support_an_owner_once() {
+        let mut ownership = guarded(3);
+        assert!(ownership.recovery.propose_recovery(CAROL).is_ok());
+
+        assert!(matches!(
+            ownership.recovery.propose_recovery(CAROL),
+            Err(RecoveryError::AlreadySupported(_))
+        ));
+        assert_eq!(ownership.recovery.recovery_supports(CAROL), U256::from(1));
+    }
+
+    #[test]
+    fn recovery_waits_for_the_veto_delay() {
+        let mut ownership = guarded(2);
+        support_from(&mut ownership, ALICE, CAROL);
+
+        assert!(ownership.recovery.propose_recovery(CAROL).is_ok());
+        let eta = testing::TIMESTAMP + MIN_RECOVERY_DELAY;
+        assert_eq!(ownership.recovery.pending_recovery().2, eta);
+        assert!(recovery_error(
+            ownership.execute_recovery(),
+            RecoveryNotReady::SELECTOR
+        ));
+
+        skip_veto_delay(&mut ownership);
+        assert!(ownership.execute_recovery().is_ok());
+        assert_eq!(ownership.owner(), CAROL);
+        assert_eq!(ownership.recovery.pending_recovery().1, Address::ZERO);
+    }
+
+    #[test]
+    fn veto_delay_has_a_minimum() {
+        let mut ownership = ownership(testing::SENDER);
+
+        let result = ownership.set_guardians(vec![ALICE], U256::from(1), MIN_RECOVERY_DELAY - 1);
+
+        assert!(recovery_error(result, RecoveryInvalidDelay::SELECTOR));
+    }
+
+    #[test]
+    fn owner_vetoes_the_recovery() {
+        let mut ownership = guarded(2);
+        support_from(&mut ownership, ALICE, CAROL);
+        assert!(ownership.recovery.propose_recovery(CAROL).is_ok());
+
+        assert!(ownership.veto_recovery().is_ok());
+
+        skip_veto_delay(&mut ownership);
+        assert!(recovery_error(
+            ownership.execute_recovery(),
+            RecoveryNotProposed::SELECTOR
+        ));
+        assert_eq!(ownership.owner(), testing::SENDER);
+    }
+
+    #[test]
+    fn guardian_changes_reset_the_support() {
+        let mut ownership = guarded(2);
+        support_from(&mut ownership, ALICE, CAROL);
+        assert!(ownership.recovery.propose_recovery(CAROL).is_ok());
+
+        let guardians = vec![testing::SENDER, ALICE, BOB];
+        assert!(ownership
+            .set_guardians(guardians, U256::from(2), MIN_RECOVERY_DELAY)
+            .is_ok());
+
+        // Alice has to support the new proposal again
+        assert!(ownership.recovery.propose_recovery(CAROL).is_ok());
+        assert_eq!(ownership.recovery.pending_recovery().1, Address::ZERO);
+        assert_eq!(ownership.recovery.recovery_supports(CAROL), U256::from(1));
+    }
+
+    #[test]
+    fn ownership_changes_cancel_the_recovery() {
+        let mut ownership = guarded(1);
+        assert!(ownership.recovery.propose_recovery(CAROL).is_ok());
+
+        ownership._transfer_ownership(ALICE);
+
+        assert_eq!(ownership.recovery.pending_recovery().1, Address::ZERO);
+    }
+
+    #[test]
+    fn only_guardians_propose_a_recovery() {
+        let mut ownership = ownership(testing::SENDER);
+        assert!(ownership
+            .set_guardians(vec![ALICE, BOB], U256::from(1), MIN_RECOVERY_DELAY)
+            .is_ok());
+
+        assert!(matches!(
+            ownership.recovery.propose_recovery(CAROL),
+            Err(RecoveryError::UnauthorizedAccount(_))
+        ));
+    }
+
+    #[test]
+    fn renounced_ownership_cannot_be_recovered() {
+        let mut ownership = guarded(1);
+        assert!(ownership.recovery.propose_recovery(CAROL).is_ok());
+        skip_veto_delay(&mut ownership);
+        // Renounced after the recovery was scheduled
+        ownership._owner.set(Address::ZERO);
+
+        let renounced = OwnershipError::OwnershipRenounced(OwnershipRenounced {});
+        assert_eq!(ownership.execute_recovery(), Err(renounced.into()));
+        assert_eq!(ownership.owner(), Address::ZERO);
+    }
+
+    #[test]
+    fn initialization_reports_erc165_and_erc173() {
+        testing::reset();
+        let mut ownership = unsafe { Ownership::new(U256::ZERO, 0) };
+        assert!(!ownership.erc165.supports_interface(ERC173_INTERFACE_ID));
+
+        assert!(ownership.initialization(testing::SENDER).is_ok());
+
+        let erc165 = &ownership.erc165;
+        assert!(erc165.supports_interface(ERC165_INTERFACE_ID));
+        assert!(erc165.supports_interface(ERC173_INTERFACE_ID));
+        assert!(!erc165.supports_interface(FixedBytes::from([0xff; 4])));
+    }
+}
@@ -0,0 +1,537 @@
+//! Multisig (M-of-N) contract.
+//!
+//! Stores a set of signers and a threshold. Signers propose calls to target
+//! contracts, confirm them, and any signer can execute a call once it has
+//! enough confirmations. Changing the signers drops every pending proposal, so
+//! confirmations never outlive the signer set they were given by.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, U256},
+    alloy_sol_types::sol,
+    evm, msg,
+    prelude::{public, sol_storage, SolidityError},
+};
+
+sol! {
+    /// Emitted when the signers or the threshold change
+    #[allow(missing_docs)]
+    event SignersUpdated(address[] signers, uint256 threshold);
+
+    /// Emitted when a signer proposes a new call
+    #[allow(missing_docs)]
+    event CallProposed(uint256 indexed id, address indexed proposer, address target, uint256 value, bytes data);
+
+    /// Emitted when a signer confirms a call
+    #[allow(missing_docs)]
+    event CallConfirmed(uint256 indexed id, address indexed signer);
+
+    /// Emitted when a signer revokes its confirmation of a call
+    #[allow(missing_docs)]
+    event ConfirmationRevoked(uint256 indexed id, address indexed signer);
+
+    /// Emitted when a call is executed
+    #[allow(missing_docs)]
+    event CallExecuted(uint256 indexed id, address indexed executor);
+}
+
+sol! {
+    /// The caller account is not a signer.
+    ///
+    /// * `account` - Account that was found to not be a signer.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error MultisigUnauthorizedAccount(address account);
+
+    /// The signer is not valid. (eg. `Address::ZERO` or duplicated)
+    ///
+    /// * `signer` - Account that's not allowed to become a signer.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error MultisigInvalidSigner(address signer);
+
+    /// The threshold is zero or bigger than the amount of signers.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error MultisigInvalidThreshold(uint256 threshold, uint256 signers);
+
+    /// The proposal does not exist.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error MultisigUnknownProposal(uint256 id);
+
+    /// The proposal was already executed.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error MultisigAlreadyExecuted(uint256 id);
+
+    /// The signers changed after the proposal was created, so it can't be executed anymore.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error MultisigStaleProposal(uint256 id);
+
+    /// The signer already confirmed the proposal.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error MultisigAlreadyConfirmed(uint256 id, address signer);
+
+    /// The signer has not confirmed the proposal.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error MultisigNotConfirmed(uint256 id, address signer);
+
+    /// The proposal does not have enough confirmations to be executed.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error MultisigNotEnoughConfirmations(uint256 id, uint256 confirmations, uint256 threshold);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum MultisigError {
+    /// The caller account is not a signer.
+    UnauthorizedAccount(MultisigUnauthorizedAccount),
+    /// The signer is not valid.
+    InvalidSigner(MultisigInvalidSigner),
+    /// The threshold is not valid for the current signers.
+    InvalidThreshold(MultisigInvalidThreshold),
+    /// The proposal does not exist.
+    UnknownProposal(MultisigUnknownProposal),
+    /// The proposal was already executed.
+    AlreadyExecuted(MultisigAlreadyExecuted),
+    /// The signers changed after the proposal was created.
+    StaleProposal(MultisigStaleProposal),
+    /// The signer already confirmed the proposal.
+    AlreadyConfirmed(MultisigAlreadyConfirmed),
+    /// The signer has not confirmed the proposal.
+    NotConfirmed(MultisigNotConfirmed),
+    /// The proposal does not have enough confirmations.
+    NotEnoughConfirmations(MultisigNotEnoughConfirmations),
+}
+
+sol_storage! {
+    pub struct Proposal {
+        /// Contract that will be called
+        address target;
+        /// Native value sent with the call
+        uint256 value;
+        /// Calldata of the call
+        bytes data;
+        /// Whether the call was already executed
+        bool executed;
+        /// Signer to confirmation status
+        mapping(address => bool) confirmed_by;
+        /// Value of `signers_nonce` when the proposal was created
+        uint256 signers_nonce;
+    }
+
+    pub struct Multisig {
+        /// Accounts allowed to propose, confirm and execute calls
+        address[] signers;
+        /// Signer to status map
+        mapping(address => bool) is_signer;
+        /// Confirmations needed to execute a call
+        uint256 threshold;
+        /// Amount of proposals created. Also used as the next proposal id
+        uint256 proposal_count;
+        /// Proposal id to proposal
+        mapping(uint256 => Proposal) proposals;
+        /// Bumped every time the signers change, so the pending proposals go stale
+        uint256 signers_nonce;
+    }
+}
+
+impl Multisig {
+    /// Checks if the [`msg::sender`] is a signer.
+    pub fn only_signer(&self) -> Result<(), MultisigError> {
+        let account = msg::sender();
+        if !self.is_signer.get(account) {
+            return Err(MultisigError::UnauthorizedAccount(
+                MultisigUnauthorizedAccount { account },
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Replaces all the signers and the threshold.
+    pub fn _set_signers(
+        &mut self,
+        signers: Vec<Address>,
+        threshold: U256,
+    ) -> Result<(), MultisigError> {
+        self.bump_signers_nonce();
+        while let Some(signer) = self.signers.pop() {
+            self.is_signer.delete(signer);
+        }
+
+        for signer in signers {
+            self.insert_signer(signer)?;
+        }
+
+        self._set_threshold(threshold)
+    }
+
+    /// Adds a new signer. The threshold is not modified.
+    pub fn _add_signer(&mut self, signer: Address) -> Result<(), MultisigError> {
+        self.insert_signer(signer)?;
+        self.bump_signers_nonce();
+
+        evm::log(SignersUpdated {
+            signers: self.signers_list(),
+            threshold: self.threshold.get(),
+        });
+
+        Ok(())
+    }
+
+    /// Removes a signer. The threshold must still be reachable afterwards.
+    pub fn _remove_signer(&mut self, signer: Address) -> Result<(), MultisigError> {
+        if !self.is_signer.get(signer) {
+            return Err(MultisigError::InvalidSigner(MultisigInvalidSigner {
+                signer,
+            }));
+        }
+
+        let last_index = self.signers.len() - 1;
+        for i in 0..last_index {
+            if self.signers.get(i) == Some(signer) {
+                let last_signer = self.signers.get(last_index).unwrap_or_default();
+                if let Some(mut slot) = self.signers.setter(i) {
+                    slot.set(last_signer);
+                }
+                break;
+            }
+        }
+        self.signers.erase_last();
+        self.is_signer.delete(signer);
+        self.bump_signers_nonce();
+
+        self._set_threshold(self.threshold.get())
+    }
+
+    /// Sets the confirmations needed to execute a call.
+    pub fn _set_threshold(&mut self, threshold: U256) -> Result<(), MultisigError> {
+        let signers = U256::from(self.signers.len());
+        if threshold.is_zero() || threshold > signers {
+            return Err(MultisigError::InvalidThreshold(MultisigInvalidThreshold {
+                threshold,
+                signers,
+            }));
+        }
+
+        self.threshold.set(threshold);
+
+        evm::log(SignersUpdated {
+            signers: self.signers_list(),
+            threshold,
+        });
+
+        Ok(())
+    }
+
+    /// Marks the proposal `id` as executed and returns the call to be made.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the proposal does not exist, was already executed or does not
+    /// have enough confirmations from the current signers.
+    pub fn _prepare_execution(
+        &mut self,
+        id: U256,
+    ) -> Result<(Address, U256, Vec<u8>), MultisigError> {
+        self.check_pending(id)?;
+
+        let confirmations = self.confirmations_count(id);
+        let threshold = self.threshold.get();
+        if confirmations < threshold {
+            return Err(MultisigError::NotEnoughConfirmations(
+                MultisigNotEnoughConfirmations {
+                    id,
+                    confirmations,
+                    threshold,
+                },
+            ));
+        }
+
+        let mut proposal = self.proposals.setter(id);
+        proposal.executed.set(true);
+
+        evm::log(CallExecuted {
+            id,
+            executor: msg::sender(),
+        });
+
+        Ok((
+            proposal.target.get(),
+            proposal.value.get(),
+            proposal.data.get_bytes(),
+        ))
+    }
+
+    fn bump_signers_nonce(&mut self) {
+        self.signers_nonce
+            .set(self.signers_nonce.get() + U256::from(1u8));
+    }
+
+    fn insert_signer(&mut self, signer: Address) -> Result<(), MultisigError> {
+        if signer.is_zero() || self.is_signer.get(signer) {
+            return Err(MultisigError::InvalidSigner(MultisigInvalidSigner {
+                signer,
+            }));
+        }
+
+        self.signers.push(signer);
+        self.is_signer.setter(signer).set(true);
+
+        Ok(())
+    }
+
+    /// Checks that the proposal `id` exists, was not executed yet and was made
+    /// by the current signers.
+    fn check_pending(&self, id: U256) -> Result<(), MultisigError> {
+        if id >= self.proposal_count.get() {
+            return Err(MultisigError::UnknownProposal(MultisigUnknownProposal {
+                id,
+            }));
+        }
+
+        let proposal = self.proposals.get(id);
+        if proposal.executed.get() {
+            return Err(MultisigError::AlreadyExecuted(MultisigAlreadyExecuted {
+                id,
+            }));
+        }
+
+        if proposal.signers_nonce.get() != self.signers_nonce.get() {
+            return Err(MultisigError::StaleProposal(MultisigStaleProposal { id }));
+        }
+
+        Ok(())
+    }
+
+    /// Counts the confirmations of the proposal `id` made by current signers.
+    /// A proposal made by previous signers has none.
+    fn confirmations_count(&self, id: U256) -> U256 {
+        let proposal = self.proposals.get(id);
+        let mut count = U256::ZERO;
+        if proposal.signers_nonce.get() != self.signers_nonce.get() {
+            return count;
+        }
+
+        for signer in self.signers_list() {
+            if proposal.confirmed_by.get(signer) {
+                count += U256::from(1u8);
+            }
+        }
+
+        count
+    }
+
+    fn signers_list(&self) -> Vec<Address> {
+        let mut signers = Vec::with_capacity(self.signers.len());
+        for i in 0..self.signers.len() {
+            if let Some(signer) = self.signers.get(i) {
+                signers.push(signer);
+            }
+        }
+
+        signers
+    }
+}
+
+#[public]
+impl Multisig {
+    /// Returns all the signers.
+    pub fn signers(&self) -> Vec<Address> {
+        self.signers_list()
+    }
+
+    /// Whether `account` is a signer.
+    pub fn is_signer(&self, account: Address) -> bool {
+        self.is_signer.get(account)
+    }
+
+    /// Returns the confirmations needed to execute a call.
+    pub fn threshold(&self) -> U256 {
+        self.threshold.get()
+    }
+
+    /// Returns the amount of proposals created.
+    pub fn proposal_count(&self) -> U256 {
+        self.proposal_count.get()
+    }
+
+    /// Returns the target, value, calldata and execution status of the proposal `id`.
+    pub fn proposal(&self, id: U256) -> (Address, U256, Bytes, bool) {
+        let proposal = self.proposals.get(id);
+        (
+            proposal.target.get(),
+            proposal.value.get(),
+            proposal.data.get_bytes().into(),
+            proposal.executed.get(),
+        )
+    }
+
+    /// Returns the amount of current signers that confirmed the proposal `id`.
+    pub fn confirmations(&self, id: U256) -> U256 {
+        self.confirmations_count(id)
+    }
+
+    /// Proposes a call to `target` with `value` and `data`. The proposer
+    /// confirms it right away. Can only be called by a signer.
+    ///
+    /// Returns the id of the new proposal.
+    pub fn propose(
+        &mut self,
+        target: Address,
+        value: U256,
+        data: Bytes,
+    ) -> Result<U256, MultisigError> {
+        self.only_signer()?;
+
+        let id = self.proposal_count.get();
+        self.proposal_count.set(id + U256::from(1u8));
+
+        let mut proposal = self.proposals.setter(id);
+        proposal.target.set(target);
+        proposal.value.set(value);
+        proposal.data.set_bytes(&data);
+        proposal.signers_nonce.set(self.signers_nonce.get());
+
+        evm::log(CallProposed {
+            id,
+            proposer: msg::sender(),
+            target,
+            value,
+            data: data.0.into(),
+        });
+
+        self.confirm(id)?;
+
+        Ok(id)
+    }
+
+    /// Confirms the proposal `id`. Can only be called by a signer.
+    pub fn confirm(&mut self, id: U256) -> Result<(), MultisigError> {
+        self.only_signer()?;
+        self.check_pending(id)?;
+
+        let signer = msg::sender();
+        let mut proposal = self.proposals.setter(id);
+        if proposal.confirmed_by.get(signer) {
+            return Err(MultisigError::AlreadyConfirmed(MultisigAlreadyConfirmed {
+                id,
+                signer,
+            }));
+        }
+        proposal.confirmed_by.setter(signer).set(true);
+
+        evm::log(CallConfirmed { id, signer });
+
+        Ok(())
+    }
+
+    /// Revokes a previous confirmation of the proposal `id`. Can only be
+    /// called by a signer.
+    pub fn revoke_confirmation(&mut self, id: U256) -> Result<(), MultisigError> {
+        self.only_signer()?;
+        self.check_pending(id)?;
+
+        let signer = msg::sender();
+        let mut proposal = self.proposals.setter(id);
+        if !proposal.confirmed_by.get(signer) {
+            return Err(MultisigError::NotConfirmed(MultisigNotConfirmed {
+                id,
+                signer,
+            }));
+        }
+        proposal.confirmed_by.delete(signer);
+
+        evm::log(ConfirmationRevoked { id, signer });
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use seabrick_common::testing::{self, SENDER};
+    use stylus_sdk::{alloy_primitives::address, storage::StorageType};
+
+    const OTHER: Address = address!("0000000000000000000000000000000000000b0b");
+    const TARGET: Address = address!("0000000000000000000000000000000000007a67");
+
+    /// Multisig of the sender and another signer, with a threshold of one
+    fn multisig() -> Multisig {
+        testing::reset();
+        let mut multisig = unsafe { Multisig::new(U256::ZERO, 0) };
+        assert!(multisig
+            ._set_signers(vec![SENDER, OTHER], U256::from(1))
+            .is_ok());
+
+        multisig
+    }
+
+    fn propose(multisig: &mut Multisig) -> U256 {
+        multisig
+            .propose(TARGET, U256::ZERO, Bytes::from(vec![1, 2, 3]))
+            .ok()
+            .unwrap()
+    }
+
+    #[test]
+    fn confirmed_proposal_can_be_executed() {
+        let mut multisig = multisig();
+        let id = propose(&mut multisig);
+
+        assert_eq!(multisig.confirmations(id), U256::from(1));
+        assert!(multisig._prepare_execution(id).is_ok());
+        assert!(matches!(
+            multisig._prepare_execution(id),
+            Err(MultisigError::AlreadyExecuted(_))
+        ));
+    }
+
+    #[test]
+    fn confirmation_of_a_removed_signer_does_not_count_once_it_is_added_back() {
+        let mut multisig = multisig();
+        let id = propose(&mut multisig);
+
+        assert!(multisig._remove_signer(SENDER).is_ok());
+        assert!(multisig._add_signer(SENDER).is_ok());
+
+        assert_eq!(multisig.confirmations(id), U256::ZERO);
+        assert!(matches!(
+            multisig._prepare_execution(id),
+            Err(MultisigError::StaleProposal(_))
+        ));
+        assert!(matches!(
+            multisig.confirm(id),
+            Err(MultisigError::StaleProposal(_))
+        ));
+    }
+
+    #[test]
+    fn proposals_do_not_survive_new_signers() {
+        let mut multisig = multisig();
+        let id = propose(&mut multisig);
+
+        // Same signers, as when the multisig mode is enabled again
+        assert!(multisig
+            ._set_signers(vec![SENDER, OTHER], U256::from(1))
+            .is_ok());
+
+        assert!(matches!(
+            multisig._prepare_execution(id),
+            Err(MultisigError::StaleProposal(_))
+        ));
+
+        let id = propose(&mut multisig);
+        assert!(multisig._prepare_execution(id).is_ok());
+    }
+}
@@ -0,0 +1,400 @@
+//! Recovery contract.
+//!
+//! Stores a set of guardians chosen by the owner. If the owner key is lost,
+//! the guardians can propose a new owner. Each guardian supports one owner at a
+//! time, so a guardian proposing someone else can't hold back the others. Once
+//! enough guardians support the same owner, the recovery can only be executed
+//! after a delay, during which the current owner can veto it.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U64},
+    alloy_sol_types::sol,
+    block, evm, msg,
+    prelude::{public, sol_storage, SolidityError},
+};
+
+/// Minimum time (in seconds) the owner has to veto a recovery. Two days.
+pub const MIN_RECOVERY_DELAY: u64 = 2 * 24 * 60 * 60;
+
+sol! {
+    /// Emitted when the guardians, the threshold or the delay change
+    #[allow(missing_docs)]
+    event GuardiansUpdated(address[] guardians, uint256 threshold, uint64 delay);
+
+    /// Emitted when a guardian proposes or supports `new_owner` as the recovered owner
+    #[allow(missing_docs)]
+    event RecoveryProposed(uint256 indexed round, address indexed guardian, address indexed new_owner);
+
+    /// Emitted when the recovery reaches the threshold and can be executed from `eta`
+    #[allow(missing_docs)]
+    event RecoveryScheduled(uint256 indexed round, address indexed new_owner, uint64 eta);
+
+    /// Emitted when the current owner vetoes the recovery
+    #[allow(missing_docs)]
+    event RecoveryVetoed(uint256 indexed round, address indexed owner);
+
+    /// Emitted when the recovery is cancelled because the guardians or the owner changed
+    #[allow(missing_docs)]
+    event RecoveryCancelled(uint256 indexed round);
+
+    /// Emitted when the recovery is executed and the ownership is transferred
+    #[allow(missing_docs)]
+    event RecoveryExecuted(uint256 indexed round, address indexed previous_owner, address indexed new_owner);
+}
+
+sol! {
+    /// The caller account is not a guardian.
+    ///
+    /// * `account` - Account that was found to not be a guardian.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error RecoveryUnauthorizedAccount(address account);
+
+    /// The guardian is not valid. (eg. `Address::ZERO` or duplicated)
+    ///
+    /// * `guardian` - Account that's not allowed to become a guardian.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error RecoveryInvalidGuardian(address guardian);
+
+    /// The threshold is zero or bigger than the amount of guardians.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error RecoveryInvalidThreshold(uint256 threshold, uint256 guardians);
+
+    /// The delay is shorter than the minimum.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error RecoveryInvalidDelay(uint64 delay, uint64 min_delay);
+
+    /// The proposed owner is not valid. (eg. `Address::ZERO`)
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error RecoveryInvalidOwner(address new_owner);
+
+    /// There is already a recovery scheduled.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error RecoveryAlreadyProposed(uint256 round, address new_owner);
+
+    /// There is no recovery scheduled.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error RecoveryNotProposed();
+
+    /// The guardian already supports the proposed owner.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error RecoveryAlreadySupported(uint256 round, address guardian, address new_owner);
+
+    /// The recovery does not have enough support from the current guardians.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error RecoveryNotEnoughSupport(uint256 round, uint256 supports, uint256 threshold);
+
+    /// The recovery delay has not passed yet.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error RecoveryNotReady(uint256 round, uint64 eta);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum RecoveryError {
+    /// The caller account is not a guardian.
+    UnauthorizedAccount(RecoveryUnauthorizedAccount),
+    /// The guardian is not valid.
+    InvalidGuardian(RecoveryInvalidGuardian),
+    /// The threshold is not valid for the guardians.
+    InvalidThreshold(RecoveryInvalidThreshold),
+    /// The delay is shorter than the minimum.
+    InvalidDelay(RecoveryInvalidDelay),
+    /// The proposed owner is not valid.
+    InvalidOwner(RecoveryInvalidOwner),
+    /// There is already a recovery scheduled.
+    AlreadyProposed(RecoveryAlreadyProposed),
+    /// There is no recovery scheduled.
+    NotProposed(RecoveryNotProposed),
+    /// The guardian already supports the proposed owner.
+    AlreadySupported(RecoveryAlreadySupported),
+    /// The recovery does not have enough support.
+    NotEnoughSupport(RecoveryNotEnoughSupport),
+    /// The recovery delay has not passed yet.
+    NotReady(RecoveryNotReady),
+}
+
+sol_storage! {
+    pub struct Recovery {
+        /// Accounts allowed to propose and support a recovery
+        address[] guardians;
+        /// Guardian to status map
+        mapping(address => bool) is_guardian;
+        /// Supports needed to schedule a recovery. Zero if there are no guardians
+        uint256 threshold;
+        /// Time (in seconds) between reaching the threshold and executing the recovery
+        uint64 delay;
+        /// Current recovery round. Bumped every time a recovery ends, so old supports are ignored
+        uint256 round;
+        /// Owner scheduled in the current round. Zero until a proposed owner reaches the threshold
+        address candidate;
+        /// Timestamp from which the scheduled recovery can be executed. Zero if there is none
+        uint64 eta;
+        /// Round to guardian to the owner it supports. Zero if it supports none
+        mapping(uint256 => mapping(address => address)) supported_by;
+    }
+}
+
+impl Recovery {
+    /// Checks if the [`msg::sender`] is a guardian.
+    pub fn only_guardian(&self) -> Result<(), RecoveryError> {
+        let account = msg::sender();
+        if !self.is_guardian.get(account) {
+            return Err(RecoveryError::UnauthorizedAccount(
+                RecoveryUnauthorizedAccount { account },
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Replaces all the guardians, the threshold and the delay. Any recovery
+    /// in progress is cancelled. An empty set of guardians with a zero
+    /// threshold disables the recovery.
+    /// Internal function without access restriction.
+    pub fn _set_guardians(
+        &mut self,
+        guardians: Vec<Address>,
+        threshold: U256,
+        delay: u64,
+    ) -> Result<(), RecoveryError> {
+        if delay < MIN_RECOVERY_DELAY {
+            return Err(RecoveryError::InvalidDelay(RecoveryInvalidDelay {
+                delay,
+                min_delay: MIN_RECOVERY_DELAY,
+            }));
+        }
+
+        let count = U256::from(guardians.len());
+        if threshold > count || (threshold.is_zero() && !count.is_zero()) {
+            return Err(RecoveryError::InvalidThreshold(RecoveryInvalidThreshold {
+                threshold,
+                guardians: count,
+            }));
+        }
+
+        while let Some(guardian) = self.guardians.pop() {
+            self.is_guardian.delete(guardian);
+        }
+
+        for guardian in guardians.iter() {
+            if guardian.is_zero() || self.is_guardian.get(*guardian) {
+                return Err(RecoveryError::InvalidGuardian(RecoveryInvalidGuardian {
+                    guardian: *guardian,
+                }));
+            }
+
+            self.guardians.push(*guardian);
+            self.is_guardian.setter(*guardian).set(true);
+        }
+
+        self.threshold.set(threshold);
+        self.delay.set(U64::from(delay));
+        self._cancel();
+
+        evm::log(GuardiansUpdated {
+            guardians,
+            threshold,
+            delay,
+        });
+
+        Ok(())
+    }
+
+    /// Cancels the recovery in progress, if any. The supports given so far are
+    /// dropped too, even if no owner reached the threshold.
+    /// Internal function without access restriction.
+    pub fn _cancel(&mut self) {
+        let round = self.round.get();
+        let scheduled = !self.candidate.get().is_zero();
+        self.end_round();
+
+        if scheduled {
+            evm::log(RecoveryCancelled { round });
+        }
+    }
+
+    /// Cancels the scheduled recovery on behalf of the current `owner`.
+    /// Internal function without access restriction.
+    pub fn _veto(&mut self, owner: Address) -> Result<(), RecoveryError> {
+        if self.candidate.get().is_zero() {
+            return Err(RecoveryError::NotProposed(RecoveryNotProposed {}));
+        }
+
+        let round = self.round.get();
+        self.end_round();
+
+        evm::log(RecoveryVetoed { round, owner });
+
+        Ok(())
+    }
+
+    /// Ends the current recovery and returns the owner to transfer the ownership to.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no recovery scheduled, it does not have enough
+    /// support from the current guardians or its delay has not passed.
+    pub fn _complete(&mut self, previous_owner: Address) -> Result<Address, RecoveryError> {
+        let new_owner = self.candidate.get();
+        if new_owner.is_zero() {
+            return Err(RecoveryError::NotProposed(RecoveryNotProposed {}));
+        }
+
+        let round = self.round.get();
+        let supports = self.supports_count(new_owner);
+        let threshold = self.threshold.get();
+        if threshold.is_zero() || supports < threshold {
+            return Err(RecoveryError::NotEnoughSupport(RecoveryNotEnoughSupport {
+                round,
+                supports,
+                threshold,
+            }));
+        }
+
+        let eta = self.eta.get().to::<u64>();
+        if eta == 0 || block::timestamp() < eta {
+            return Err(RecoveryError::NotReady(RecoveryNotReady { round, eta }));
+        }
+
+        self.end_round();
+
+        evm::log(RecoveryExecuted {
+            round,
+            previous_owner,
+            new_owner,
+        });
+
+        Ok(new_owner)
+    }
+
+    fn end_round(&mut self) {
+        self.candidate.set(Address::ZERO);
+        self.eta.set(U64::ZERO);
+        self.round.set(self.round.get() + U256::from(1u8));
+    }
+
+    /// Counts the current guardians supporting `new_owner` in the current round.
+    /// Supports from removed guardians are ignored.
+    fn supports_count(&self, new_owner: Address) -> U256 {
+        let round = self.round.get();
+        let supported_by = self.supported_by.getter(round);
+        let mut count = U256::ZERO;
+        for guardian in self.guardians_list() {
+            if supported_by.get(guardian) == new_owner {
+                count += U256::from(1u8);
+            }
+        }
+
+        count
+    }
+
+    fn guardians_list(&self) -> Vec<Address> {
+        let mut guardians = Vec::with_capacity(self.guardians.len());
+        for i in 0..self.guardians.len() {
+            if let Some(guardian) = self.guardians.get(i) {
+                guardians.push(guardian);
+            }
+        }
+
+        guardians
+    }
+}
+
+#[public]
+impl Recovery {
+    /// Returns all the guardians.
+    pub fn guardians(&self) -> Vec<Address> {
+        self.guardians_list()
+    }
+
+    /// Whether `account` is a guardian.
+    pub fn is_guardian(&self, account: Address) -> bool {
+        self.is_guardian.get(account)
+    }
+
+    /// Returns the guardian supports needed to schedule a recovery.
+    pub fn recovery_threshold(&self) -> U256 {
+        self.threshold.get()
+    }
+
+    /// Returns the time (in seconds) the owner has to veto a scheduled recovery.
+    pub fn recovery_delay(&self) -> u64 {
+        self.delay.get().to()
+    }
+
+    /// Returns the round, scheduled owner and ETA of the current recovery. The
+    /// owner is the zero address and the ETA is zero if there is no recovery scheduled.
+    pub fn pending_recovery(&self) -> (U256, Address, u64) {
+        (self.round.get(), self.candidate.get(), self.eta.get().to())
+    }
+
+    /// Returns the current guardians supporting `new_owner` in the current round.
+    pub fn recovery_supports(&self, new_owner: Address) -> U256 {
+        self.supports_count(new_owner)
+    }
+
+    /// Proposes `new_owner` as the recovered owner, or supports it if other
+    /// guardians already did. A guardian supports one owner at a time, so this
+    /// moves its support from any owner it proposed before. The recovery is
+    /// scheduled once `new_owner` reaches the threshold. Can only be called by a guardian.
+    pub fn propose_recovery(&mut self, new_owner: Address) -> Result<(), RecoveryError> {
+        self.only_guardian()?;
+
+        if new_owner.is_zero() {
+            return Err(RecoveryError::InvalidOwner(RecoveryInvalidOwner {
+                new_owner,
+            }));
+        }
+
+        let round = self.round.get();
+        let candidate = self.candidate.get();
+        if !candidate.is_zero() {
+            return Err(RecoveryError::AlreadyProposed(RecoveryAlreadyProposed {
+                round,
+                new_owner: candidate,
+            }));
+        }
+
+        let guardian = msg::sender();
+        if self.supported_by.getter(round).get(guardian) == new_owner {
+            return Err(RecoveryError::AlreadySupported(RecoveryAlreadySupported {
+                round,
+                guardian,
+                new_owner,
+            }));
+        }
+        self.supported_by.setter(round).insert(guardian, new_owner);
+
+        evm::log(RecoveryProposed {
+            round,
+            guardian,
+            new_owner,
+        });
+
+        if self.supports_count(new_owner) >= self.threshold.get() {
+            let eta = block::timestamp().saturating_add(self.delay.get().to());
+            self.candidate.set(new_owner);
+            self.eta.set(U64::from(eta));
+
+            evm::log(RecoveryScheduled {
+                round,
+                new_owner,
+                eta,
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+//! Registry contract.
+//!
+//! Keeps the named addresses of the contracts managed by this ownership
+//! contract, so the whole deployment can be discovered from a single address.
+//! Any `bytes32` can be used as a name, the well known ones are the keccak256
+//! hash of the contract name.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use stylus_sdk::{
+    alloy_primitives::{fixed_bytes, Address, FixedBytes, U256},
+    alloy_sol_types::sol,
+    evm,
+    prelude::{public, sol_storage, SolidityError},
+};
+
+/// keccak256("SEABRICK_NFT"). The Seabrick NFT contract.
+pub const SEABRICK_NFT: FixedBytes<32> =
+    fixed_bytes!("ad7bfecc733328d60436318078ee93c653719ee9750305adab7a719140726dd6");
+
+/// keccak256("MARKET"). The Seabrick market contract.
+pub const MARKET: FixedBytes<32> =
+    fixed_bytes!("23acc66832ff38a7ce3c1caf89922410f4374ba8fd897ffa98f088747997bc6d");
+
+/// keccak256("VAULT"). The account that receives the market funds.
+pub const VAULT: FixedBytes<32> =
+    fixed_bytes!("68fc488efe30251cadb6ac88bdeef3f1a5e6048808baf387258d1d78e986720c");
+
+sol! {
+    /// Emitted when `name` is registered or updated to `addr`.
+    #[allow(missing_docs)]
+    event ContractRegistered(bytes32 indexed name, address indexed previous_addr, address indexed addr);
+
+    /// Emitted when `name` is removed from the registry.
+    #[allow(missing_docs)]
+    event ContractRemoved(bytes32 indexed name, address indexed addr);
+}
+
+sol! {
+    /// The address can't be registered. (eg. `Address::ZERO`)
+    ///
+    /// * `addr` - The invalid address.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error RegistryInvalidContract(address addr);
+
+    /// There is no contract registered with the name.
+    ///
+    /// * `name` - The unknown name.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error RegistryUnknownContract(bytes32 name);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum RegistryError {
+    /// The address can't be registered.
+    InvalidContract(RegistryInvalidContract),
+    /// There is no contract registered with the name.
+    UnknownContract(RegistryUnknownContract),
+}
+
+sol_storage! {
+    pub struct Registry {
+        /// Registered names
+        bytes32[] names;
+        /// Name to its position in `names` plus one (zero means not registered)
+        mapping(bytes32 => uint256) positions;
+        /// Name to contract address
+        mapping(bytes32 => address) contracts;
+    }
+}
+
+impl Registry {
+    /// Registers `addr` as `name`, replacing the previous address if any.
+    /// Internal function without access restriction.
+    pub fn _set_contract(
+        &mut self,
+        name: FixedBytes<32>,
+        addr: Address,
+    ) -> Result<(), RegistryError> {
+        if addr.is_zero() {
+            return Err(RegistryError::InvalidContract(RegistryInvalidContract {
+                addr,
+            }));
+        }
+
+        if self.positions.get(name).is_zero() {
+            self.names.push(name);
+            self.positions
+                .setter(name)
+                .set(U256::from(self.names.len()));
+        }
+
+        let previous_addr = self.contracts.get(name);
+        self.contracts.setter(name).set(addr);
+
+        evm::log(ContractRegistered {
+            name,
+            previous_addr,
+            addr,
+        });
+
+        Ok(())
+    }
+
+    /// Removes `name` from the registry by swapping it with the last one.
+    /// Internal function without access restriction.
+    pub fn _remove_contract(&mut self, name: FixedBytes<32>) -> Result<(), RegistryError> {
+        let position = self.positions.get(name);
+        if position.is_zero() {
+            return Err(RegistryError::UnknownContract(RegistryUnknownContract {
+                name,
+            }));
+        }
+
+        let index = position - U256::from(1u8);
+        let last_index = self.names.len() - 1;
+
+        if let Some(last_name) = self.names.get(last_index) {
+            if index != U256::from(last_index) {
+                if let Some(mut slot) = self.names.setter(index) {
+                    slot.set(last_name);
+                }
+                self.positions.setter(last_name).set(position);
+            }
+        }
+
+        self.names.erase_last();
+        self.positions.delete(name);
+
+        let addr = self.contracts.get(name);
+        self.contracts.delete(name);
+
+        evm::log(ContractRemoved { name, addr });
+
+        Ok(())
+    }
+
+    fn names_list(&self) -> Vec<FixedBytes<32>> {
+        let mut names = Vec::with_capacity(self.names.len());
+        for i in 0..self.names.len() {
+            if let Some(name) = self.names.get(i) {
+                names.push(name);
+            }
+        }
+
+        names
+    }
+}
+
+#[public]
+impl Registry {
+    /// Returns the address registered as `name`, or the zero address if there is none.
+    pub fn get_contract(&self, name: FixedBytes<32>) -> Address {
+        self.contracts.get(name)
+    }
+
+    /// Whether there is a contract registered as `name`.
+    pub fn is_registered(&self, name: FixedBytes<32>) -> bool {
+        !self.positions.get(name).is_zero()
+    }
+
+    /// Returns all the registered names.
+    pub fn contract_names(&self) -> Vec<FixedBytes<32>> {
+        self.names_list()
+    }
+
+    /// Returns all the registered names and their addresses, in the same order.
+    pub fn contracts(&self) -> (Vec<FixedBytes<32>>, Vec<Address>) {
+        let names = self.names_list();
+        let addrs = names.iter().map(|name| self.contracts.get(*name)).collect();
+
+        (names, addrs)
+    }
+
+    #[selector(name = "SEABRICK_NFT")]
+    pub fn seabrick_nft(&self) -> FixedBytes<32> {
+        SEABRICK_NFT
+    }
+
+    #[selector(name = "MARKET")]
+    pub fn market(&self) -> FixedBytes<32> {
+        MARKET
+    }
+
+    #[selector(name = "VAULT")]
+    pub fn vault(&self) -> FixedBytes<32> {
+        VAULT
+    }
+}
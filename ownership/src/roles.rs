@@ -0,0 +1,93 @@
+//! Roles contract.
+//!
+//! Keeps which accounts hold each role, so the dependent contracts can require
+//! the narrowest permission for each action instead of the owner.
+
+extern crate alloc;
+
+pub use seabrick_common::ownable::roles::{MINTER_ADMIN, PAUSER, PRICE_ADMIN, VAULT_ADMIN};
+use stylus_sdk::{
+    alloy_primitives::{Address, FixedBytes},
+    alloy_sol_types::sol,
+    evm, msg,
+    prelude::{public, sol_storage},
+};
+
+sol! {
+    /// Emitted when `account` is granted `role`.
+    #[allow(missing_docs)]
+    event RoleGranted(bytes32 indexed role, address indexed account, address indexed sender);
+
+    /// Emitted when `account` is revoked `role`.
+    #[allow(missing_docs)]
+    event RoleRevoked(bytes32 indexed role, address indexed account, address indexed sender);
+}
+
+sol_storage! {
+    pub struct Roles {
+        /// Role to account to status map
+        mapping(bytes32 => mapping(address => bool)) members;
+    }
+}
+
+impl Roles {
+    /// Grants `role` to `account`.
+    /// Internal function without access restriction.
+    pub fn _grant_role(&mut self, role: FixedBytes<32>, account: Address) {
+        if self.members.getter(role).get(account) {
+            return;
+        }
+
+        self.members.setter(role).insert(account, true);
+
+        evm::log(RoleGranted {
+            role,
+            account,
+            sender: msg::sender(),
+        });
+    }
+
+    /// Revokes `role` from `account`.
+    /// Internal function without access restriction.
+    pub fn _revoke_role(&mut self, role: FixedBytes<32>, account: Address) {
+        if !self.members.getter(role).get(account) {
+            return;
+        }
+
+        self.members.setter(role).delete(account);
+
+        evm::log(RoleRevoked {
+            role,
+            account,
+            sender: msg::sender(),
+        });
+    }
+}
+
+#[public]
+impl Roles {
+    /// Whether `account` has been granted `role`.
+    pub fn has_role(&self, role: FixedBytes<32>, account: Address) -> bool {
+        self.members.getter(role).get(account)
+    }
+
+    #[selector(name = "PRICE_ADMIN")]
+    pub fn price_admin(&self) -> FixedBytes<32> {
+        PRICE_ADMIN
+    }
+
+    #[selector(name = "VAULT_ADMIN")]
+    pub fn vault_admin(&self) -> FixedBytes<32> {
+        VAULT_ADMIN
+    }
+
+    #[selector(name = "MINTER_ADMIN")]
+    pub fn minter_admin(&self) -> FixedBytes<32> {
+        MINTER_ADMIN
+    }
+
+    #[selector(name = "PAUSER")]
+    pub fn pauser(&self) -> FixedBytes<32> {
+        PAUSER
+    }
+}
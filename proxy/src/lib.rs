@@ -0,0 +1,610 @@
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(feature = "export-abi"), no_std)]
+
+//! Upgradeable proxy for the Seabrick Stylus contracts.
+//!
+//! Every call is delegated to the implementation contract, so the state lives in
+//! the proxy and the code can be replaced. The proxy only answers a few methods
+//! itself (`initializeProxy`, `upgradeTo`, `upgradeToAndCall`, `changeAdmin`,
+//! `implementation` and `proxyAdmin`), and only to its admin: the owner and the
+//! ownership contract. Any other caller is delegated to the implementation, even
+//! with one of those selectors, so a method of the implementation can't be
+//! shadowed for its users. The admin can't reach such a method through the proxy.
+//!
+//! The implementation and the ownership contract addresses are kept in the ERC-1967
+//! implementation and admin slots, so they never collide with the implementation
+//! storage (which starts at slot zero).
+//! Upgrades are allowed to the owner returned by the ownership contract (or to the
+//! ownership contract itself), the same as the `Ownable` component of the implementation.
+//! The proxy keeps its own copy of the ownership contract, so moving the implementation
+//! to a new ownership contract must be followed by `changeAdmin`.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use seabrick_common::{
+    erc165,
+    initialization::initializer,
+    ownable::{Ownership, OwnershipCall, ERC173_INTERFACE_ID},
+};
+use stylus_sdk::{
+    alloy_primitives::{uint, Address, B256, U256},
+    call::RawCall,
+    evm, msg,
+    prelude::{entrypoint, SolidityError},
+    storage::{GlobalStorage, StorageCache},
+    types::AddressVM,
+    ArbResult,
+};
+
+/// keccak256("eip1967.proxy.implementation") - 1
+const IMPLEMENTATION_SLOT: U256 =
+    uint!(0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc_U256);
+
+/// keccak256("eip1967.proxy.admin") - 1. Holds the ownership contract address.
+const ADMIN_SLOT: U256 =
+    uint!(0xb53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103_U256);
+
+sol! {
+    /// Emitted when the implementation is upgraded
+    event Upgraded(address indexed implementation);
+
+    /// Emitted when the ownership contract (ERC-1967 admin) changes
+    event AdminChanged(address previousAdmin, address newAdmin);
+}
+
+sol! {
+    /// The caller account is not authorized to perform an operation.
+    error ProxyUnauthorizedAccount(address account);
+
+    /// The proxy was already initialized
+    error ProxyAlreadyInit();
+
+    /// The implementation is not a contract
+    error ProxyInvalidImplementation(address implementation);
+
+    /// The call data could not be decoded
+    error ProxyInvalidCalldata();
+
+    /// The new admin is not an ownership contract with the same owner
+    error ProxyInvalidAdmin(address admin);
+}
+
+sol! {
+    /// Methods answered by the proxy itself
+    function initializeProxy(address ownership, address implementation, bytes data);
+    function upgradeTo(address implementation);
+    function upgradeToAndCall(address implementation, bytes data);
+    function changeAdmin(address admin);
+    function implementation() external view returns (address);
+    function proxyAdmin() external view returns (address);
+}
+
+#[derive(SolidityError)]
+pub enum ProxyError {
+    UnauthorizedAccount(ProxyUnauthorizedAccount),
+    AlreadyInit(ProxyAlreadyInit),
+    InvalidImplementation(ProxyInvalidImplementation),
+    InvalidCalldata(ProxyInvalidCalldata),
+    InvalidAdmin(ProxyInvalidAdmin),
+}
+
+#[entrypoint]
+fn user_main(input: Vec<u8>) -> ArbResult {
+    let selector: [u8; 4] = input
+        .get(..4)
+        .and_then(|selector| selector.try_into().ok())
+        .unwrap_or_default();
+
+    if !is_proxy_method(selector) || !answers_caller() {
+        return forward(&input);
+    }
+
+    match selector {
+        initializeProxyCall::SELECTOR => {
+            let call = decode::<initializeProxyCall>(&input)?;
+            initialize(call.ownership, call.implementation, &call.data)
+        }
+        upgradeToCall::SELECTOR => {
+            let call = decode::<upgradeToCall>(&input)?;
+            only_owner()?;
+            upgrade_to(call.implementation)?;
+            Ok(Vec::new())
+        }
+        upgradeToAndCallCall::SELECTOR => {
+            let call = decode::<upgradeToAndCallCall>(&input)?;
+            only_owner()?;
+            upgrade_to(call.implementation)?;
+            forward(&call.data)
+        }
+        changeAdminCall::SELECTOR => {
+            let call = decode::<changeAdminCall>(&input)?;
+            change_admin(call.admin)?;
+            Ok(Vec::new())
+        }
+        implementationCall::SELECTOR => Ok(implementationCall::abi_encode_returns(&(get_address(
+            IMPLEMENTATION_SLOT,
+        ),))),
+        proxyAdminCall::SELECTOR => Ok(proxyAdminCall::abi_encode_returns(&(get_address(
+            ADMIN_SLOT,
+        ),))),
+        _ => forward(&input),
+    }
+}
+
+/// Sets the ownership contract and the first implementation, and delegates `data`
+/// (usually the implementation `initialization` call) if it's not empty.
+/// Can only be called once, by the initializer set at build time (the same
+/// one of the other Seabrick contracts).
+fn initialize(ownership: Address, implementation: Address, data: &[u8]) -> ArbResult {
+    if !get_address(IMPLEMENTATION_SLOT).is_zero() {
+        return Err(ProxyError::AlreadyInit(ProxyAlreadyInit {}).into());
+    }
+
+    let account = msg::sender();
+    if Some(account) != initializer() {
+        return Err(ProxyError::UnauthorizedAccount(ProxyUnauthorizedAccount { account }).into());
+    }
+
+    set_address(ADMIN_SLOT, ownership);
+    evm::log(AdminChanged {
+        previousAdmin: Address::ZERO,
+        newAdmin: ownership,
+    });
+
+    upgrade_to(implementation)?;
+
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    forward(data)
+}
+
+/// Whether `selector` is one of the methods answered by the proxy itself
+fn is_proxy_method(selector: [u8; 4]) -> bool {
+    matches!(
+        selector,
+        initializeProxyCall::SELECTOR
+            | upgradeToCall::SELECTOR
+            | upgradeToAndCallCall::SELECTOR
+            | changeAdminCall::SELECTOR
+            | implementationCall::SELECTOR
+            | proxyAdminCall::SELECTOR
+    )
+}
+
+/// Whether the proxy methods are answered to the [`msg::sender`], which must be
+/// the admin. Until the proxy is initialized there is no implementation to
+/// delegate to, so they are answered to everyone, and each one checks its caller.
+fn answers_caller() -> bool {
+    get_address(IMPLEMENTATION_SLOT).is_zero() || only_owner().is_ok()
+}
+
+/// Checks if the [`msg::sender`] is the owner set in the ownership contract,
+/// or the ownership contract itself. Returns the owner.
+fn only_owner() -> Result<Address, Vec<u8>> {
+    let account = msg::sender();
+    let ownership = get_address(ADMIN_SLOT);
+    let owner = Ownership::new(ownership).owner(OwnershipCall)?;
+
+    if owner.is_zero() || (owner != account && ownership != account) {
+        return Err(ProxyError::UnauthorizedAccount(ProxyUnauthorizedAccount { account }).into());
+    }
+
+    Ok(owner)
+}
+
+/// Changes the ownership contract allowed to upgrade the proxy. The new one must
+/// implement ERC-173 and have the same owner, so the upgrade rights can't be lost.
+fn change_admin(admin: Address) -> Result<(), Vec<u8>> {
+    let owner = only_owner()?;
+
+    let invalid = || ProxyError::InvalidAdmin(ProxyInvalidAdmin { admin }).into();
+    if !erc165::supports_interface(admin, ERC173_INTERFACE_ID) {
+        return Err(invalid());
+    }
+    if Ownership::new(admin).owner(OwnershipCall) != Ok(owner) {
+        return Err(invalid());
+    }
+
+    let previous_admin = get_address(ADMIN_SLOT);
+    set_address(ADMIN_SLOT, admin);
+    evm::log(AdminChanged {
+        previousAdmin: previous_admin,
+        newAdmin: admin,
+    });
+
+    Ok(())
+}
+
+/// Changes the implementation contract
+fn upgrade_to(implementation: Address) -> Result<(), Vec<u8>> {
+    if !implementation.has_code() {
+        return Err(
+            ProxyError::InvalidImplementation(ProxyInvalidImplementation { implementation }).into(),
+        );
+    }
+
+    set_address(IMPLEMENTATION_SLOT, implementation);
+
+    evm::log(Upgraded { implementation });
+
+    Ok(())
+}
+
+/// Delegates `data` to the current implementation, returning its output as is.
+fn forward(data: &[u8]) -> ArbResult {
+    // Persist our own writes before the implementation touches the storage
+    StorageCache::clear();
+
+    let implementation = get_address(IMPLEMENTATION_SLOT);
+    unsafe { RawCall::new_delegate().call(implementation, data) }
+}
+
+fn decode<C: SolCall>(input: &[u8]) -> Result<C, Vec<u8>> {
+    C::abi_decode(input, true)
+        .map_err(|_| ProxyError::InvalidCalldata(ProxyInvalidCalldata {}).into())
+}
+
+fn get_address(slot: U256) -> Address {
+    Address::from_word(StorageCache::get_word(slot))
+}
+
+fn set_address(slot: U256, address: Address) {
+    unsafe { StorageCache::set_word(slot, B256::from(address.into_word())) };
+}
+
+/// The methods answered by the proxy itself, declared only for the ABI export.
+/// They're never routed: [`user_main`] decodes the same calls.
+#[cfg(feature = "export-abi")]
+#[allow(unused_variables)]
+mod abi {
+    use alloc::vec::Vec;
+    use stylus_sdk::{
+        abi::Bytes,
+        alloy_primitives::Address,
+        prelude::{public, sol_storage},
+    };
+
+    sol_storage! {
+        pub struct SeabrickProxy {}
+    }
+
+    #[public]
+    impl SeabrickProxy {
+        pub fn initialize_proxy(
+            &mut self,
+            ownership: Address,
+            implementation: Address,
+            data: Bytes,
+        ) -> Result<(), Vec<u8>> {
+            unreachable!()
+        }
+
+        pub fn upgrade_to(&mut self, implementation: Address) -> Result<(), Vec<u8>> {
+            unreachable!()
+        }
+
+        #[payable]
+        pub fn upgrade_to_and_call(
+            &mut self,
+            implementation: Address,
+            data: Bytes,
+        ) -> Result<(), Vec<u8>> {
+            unreachable!()
+        }
+
+        pub fn change_admin(&mut self, admin: Address) -> Result<(), Vec<u8>> {
+            unreachable!()
+        }
+
+        pub fn implementation(&self) -> Address {
+            unreachable!()
+        }
+
+        pub fn proxy_admin(&self) -> Address {
+            unreachable!()
+        }
+    }
+}
+
+/// Prints the Solidity interface of the methods answered by the proxy itself.
+#[cfg(feature = "export-abi")]
+pub fn print_abi(license: &str, pragma: &str) {
+    stylus_sdk::abi::export::print_abi::<abi::SeabrickProxy>(license, pragma);
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloy_sol_types::{SolEvent, SolValue};
+    use seabrick_common::{
+        erc165::{supportsInterfaceCall, ERC165_INTERFACE_ID},
+        testing,
+    };
+    use stylus_sdk::alloy_primitives::address;
+
+    const OWNERSHIP: Address = address!("00000000000000000000000000000000000000e0");
+    const NEW_OWNERSHIP: Address = address!("00000000000000000000000000000000000000e1");
+    const IMPLEMENTATION: Address = address!("0000000000000000000000000000000000000001");
+    const NEW_IMPLEMENTATION: Address = address!("0000000000000000000000000000000000000002");
+    const BOB: Address = address!("0000000000000000000000000000000000000b0b");
+
+    /// Makes the ownership contract report `owner` as the owner. The
+    /// implementations answer with their address followed by the calldata.
+    fn contracts(owner: Address) {
+        testing::on_call(move |to, calldata| match to {
+            OWNERSHIP => ownership(owner, calldata),
+            _ => Ok([to.as_slice(), calldata].concat()),
+        });
+    }
+
+    /// Answer of an ownership contract owned by `owner`, which reports ERC-173
+    fn ownership(owner: Address, calldata: &[u8]) -> Result<Vec<u8>, Vec<u8>> {
+        match supportsInterfaceCall::abi_decode(calldata, true) {
+            Ok(call) => {
+                let ids = [ERC165_INTERFACE_ID, ERC173_INTERFACE_ID];
+                Ok(ids.contains(&call.interface_id).abi_encode())
+            }
+            Err(_) => Ok(owner.abi_encode()),
+        }
+    }
+
+    fn proxy_admin() -> Address {
+        let output = user_main(proxyAdminCall {}.abi_encode()).unwrap();
+        proxyAdminCall::abi_decode_returns(&output, true)
+            .unwrap()
+            ._0
+    }
+
+    /// A proxy initialized with [`IMPLEMENTATION`], where `owner` is the owner
+    fn proxy(owner: Address) {
+        testing::reset();
+        contracts(owner);
+        let call = initializeProxyCall {
+            ownership: OWNERSHIP,
+            implementation: IMPLEMENTATION,
+            data: vec![].into(),
+        };
+        assert_eq!(user_main(call.abi_encode()), Ok(vec![]));
+    }
+
+    fn implementation() -> Address {
+        let output = user_main(implementationCall {}.abi_encode()).unwrap();
+        implementationCall::abi_decode_returns(&output, true)
+            .unwrap()
+            ._0
+    }
+
+    #[test]
+    fn initialization_sets_the_contracts_and_delegates_the_data() {
+        testing::reset();
+        contracts(testing::SENDER);
+
+        let call = initializeProxyCall {
+            ownership: OWNERSHIP,
+            implementation: IMPLEMENTATION,
+            data: b"init".to_vec().into(),
+        };
+        assert_eq!(
+            user_main(call.abi_encode()),
+            Ok([IMPLEMENTATION.as_slice(), b"init"].concat())
+        );
+
+        assert_eq!(implementation(), IMPLEMENTATION);
+        assert_eq!(proxy_admin(), OWNERSHIP);
+
+        let topics: Vec<[u8; 32]> = testing::take_logs()
+            .into_iter()
+            .map(|log| log.topics[0])
+            .collect();
+        assert_eq!(
+            topics,
+            [AdminChanged::SIGNATURE_HASH.0, Upgraded::SIGNATURE_HASH.0]
+        );
+    }
+
+    #[test]
+    fn proxy_is_initialized_once() {
+        proxy(testing::SENDER);
+
+        let call = initializeProxyCall {
+            ownership: BOB,
+            implementation: NEW_IMPLEMENTATION,
+            data: vec![].into(),
+        };
+        assert_eq!(
+            user_main(call.abi_encode()),
+            Err(ProxyError::AlreadyInit(ProxyAlreadyInit {}).into())
+        );
+        assert_eq!(implementation(), IMPLEMENTATION);
+    }
+
+    #[test]
+    fn implementation_must_be_a_contract() {
+        // Without a call handler no account has code
+        testing::reset();
+
+        let call = initializeProxyCall {
+            ownership: OWNERSHIP,
+            implementation: IMPLEMENTATION,
+            data: vec![].into(),
+        };
+        assert_eq!(
+            user_main(call.abi_encode()),
+            Err(
+                ProxyError::InvalidImplementation(ProxyInvalidImplementation {
+                    implementation: IMPLEMENTATION
+                })
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn other_calls_are_delegated_to_the_implementation() {
+        proxy(testing::SENDER);
+
+        let input = b"\x12\x34\x56\x78call".to_vec();
+        assert_eq!(
+            user_main(input.clone()),
+            Ok([IMPLEMENTATION.as_slice(), &input].concat())
+        );
+    }
+
+    #[test]
+    fn owner_upgrades_the_implementation() {
+        proxy(testing::SENDER);
+
+        let call = upgradeToCall {
+            implementation: NEW_IMPLEMENTATION,
+        };
+        assert_eq!(user_main(call.abi_encode()), Ok(vec![]));
+        assert_eq!(implementation(), NEW_IMPLEMENTATION);
+
+        let call = upgradeToAndCallCall {
+            implementation: IMPLEMENTATION,
+            data: b"migrate".to_vec().into(),
+        };
+        assert_eq!(
+            user_main(call.abi_encode()),
+            Ok([IMPLEMENTATION.as_slice(), b"migrate"].concat())
+        );
+        assert_eq!(implementation(), IMPLEMENTATION);
+    }
+
+    #[test]
+    fn only_the_owner_upgrades() {
+        proxy(BOB);
+
+        // Anyone else reaches the implementation, even with the proxy selectors
+        let upgrade = upgradeToCall {
+            implementation: NEW_IMPLEMENTATION,
+        }
+        .abi_encode();
+        let upgrade_and_call = upgradeToAndCallCall {
+            implementation: NEW_IMPLEMENTATION,
+            data: vec![].into(),
+        }
+        .abi_encode();
+        for input in [upgrade, upgrade_and_call] {
+            assert_eq!(
+                user_main(input.clone()),
+                Ok([IMPLEMENTATION.as_slice(), &input].concat())
+            );
+        }
+
+        assert_eq!(get_address(IMPLEMENTATION_SLOT), IMPLEMENTATION);
+    }
+
+    #[test]
+    fn proxy_methods_are_delegated_for_other_callers() {
+        proxy(BOB);
+
+        for input in [
+            implementationCall {}.abi_encode(),
+            proxyAdminCall {}.abi_encode(),
+            initializeProxyCall {
+                ownership: BOB,
+                implementation: NEW_IMPLEMENTATION,
+                data: vec![].into(),
+            }
+            .abi_encode(),
+        ] {
+            assert_eq!(
+                user_main(input.clone()),
+                Ok([IMPLEMENTATION.as_slice(), &input].concat())
+            );
+        }
+
+        // Once the ownership is renounced, no one is the admin
+        contracts(Address::ZERO);
+        let input = implementationCall {}.abi_encode();
+        assert_eq!(
+            user_main(input.clone()),
+            Ok([IMPLEMENTATION.as_slice(), &input].concat())
+        );
+    }
+
+    #[test]
+    fn ownership_contract_upgrades_as_the_owner() {
+        // The ownership contract calls the proxy, eg. in a batch
+        testing::reset();
+        testing::on_call(|to, calldata| match to {
+            testing::SENDER => Ok(BOB.abi_encode()),
+            _ => Ok([to.as_slice(), calldata].concat()),
+        });
+        let call = initializeProxyCall {
+            ownership: testing::SENDER,
+            implementation: IMPLEMENTATION,
+            data: vec![].into(),
+        };
+        assert_eq!(user_main(call.abi_encode()), Ok(vec![]));
+
+        let call = upgradeToCall {
+            implementation: NEW_IMPLEMENTATION,
+        };
+        assert_eq!(user_main(call.abi_encode()), Ok(vec![]));
+        assert_eq!(implementation(), NEW_IMPLEMENTATION);
+    }
+
+    #[test]
+    fn owner_changes_the_admin() {
+        proxy(testing::SENDER);
+        testing::on_call(|to, calldata| match to {
+            OWNERSHIP | NEW_OWNERSHIP => ownership(testing::SENDER, calldata),
+            _ => Ok([to.as_slice(), calldata].concat()),
+        });
+        testing::take_logs();
+
+        let call = changeAdminCall {
+            admin: NEW_OWNERSHIP,
+        };
+        assert_eq!(user_main(call.abi_encode()), Ok(vec![]));
+
+        assert_eq!(proxy_admin(), NEW_OWNERSHIP);
+        let logs = testing::take_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(
+            AdminChanged::abi_decode_data(&logs[0].data, true).unwrap(),
+            (OWNERSHIP, NEW_OWNERSHIP)
+        );
+    }
+
+    #[test]
+    fn new_admin_must_be_an_ownership_contract_with_the_same_owner() {
+        proxy(testing::SENDER);
+        testing::on_call(|to, calldata| match to {
+            OWNERSHIP => ownership(testing::SENDER, calldata),
+            NEW_OWNERSHIP => ownership(BOB, calldata),
+            _ => Ok([to.as_slice(), calldata].concat()),
+        });
+
+        for admin in [NEW_OWNERSHIP, IMPLEMENTATION] {
+            let call = changeAdminCall { admin };
+            assert_eq!(
+                user_main(call.abi_encode()),
+                Err(ProxyError::InvalidAdmin(ProxyInvalidAdmin { admin }).into())
+            );
+        }
+        assert_eq!(proxy_admin(), OWNERSHIP);
+    }
+
+    #[test]
+    fn only_the_owner_changes_the_admin() {
+        proxy(BOB);
+
+        let input = changeAdminCall {
+            admin: NEW_OWNERSHIP,
+        }
+        .abi_encode();
+        assert_eq!(
+            user_main(input.clone()),
+            Ok([IMPLEMENTATION.as_slice(), &input].concat())
+        );
+        assert_eq!(get_address(ADMIN_SLOT), OWNERSHIP);
+    }
+}
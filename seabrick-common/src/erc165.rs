@@ -0,0 +1,158 @@
+//! ERC-165 contract.
+//!
+//! Keeps the interfaces the contract reports as supported. ERC-165 itself is
+//! always supported, the rest must be registered by the contract.
+//! [`supports_interface`] checks the same on other contracts.
+
+extern crate alloc;
+
+use stylus_sdk::{
+    alloy_primitives::{fixed_bytes, Address, FixedBytes},
+    alloy_sol_types::{sol, SolCall},
+    call::{static_call, CallContext, StaticCallContext},
+    prelude::{public, sol_storage},
+    types::AddressVM,
+};
+
+sol! {
+    /// Encoded with `sol!`, since `sol_interface!` builds the selector of a `bytesN`
+    /// argument as `bytes[N]`.
+    function supportsInterface(bytes4 interface_id) external view returns (bool);
+}
+
+/// ERC-165 interface id.
+pub const ERC165_INTERFACE_ID: FixedBytes<4> = fixed_bytes!("01ffc9a7");
+
+/// Invalid interface id. It must never be reported as supported.
+pub const INVALID_INTERFACE_ID: FixedBytes<4> = fixed_bytes!("ffffffff");
+
+/// Gas forwarded to each `supportsInterface` call. ERC-165 asks for 30000, which
+/// is enough for an EVM contract but not to start a Stylus one.
+pub const ERC165_CALL_GAS: u64 = 100_000;
+
+/// Context for the `supportsInterface` calls. They are static calls, so they
+/// can be made from a component (see `ownable::OwnershipCall`).
+struct Erc165Call;
+
+impl CallContext for Erc165Call {
+    fn gas(&self) -> u64 {
+        ERC165_CALL_GAS
+    }
+}
+
+impl StaticCallContext for Erc165Call {}
+
+/// Whether `account` is a contract that implements `interface_id`, following the
+/// detection steps of ERC-165: it must support ERC-165 itself, must not support
+/// the invalid id `0xffffffff`, and must support `interface_id`.
+/// A call that reverts or returns garbage counts as not supported.
+pub fn supports_interface(account: Address, interface_id: FixedBytes<4>) -> bool {
+    if !account.has_code() {
+        return false;
+    }
+
+    let query = |interface_id| {
+        let calldata = supportsInterfaceCall { interface_id }.abi_encode();
+        static_call(Erc165Call, account, &calldata)
+            .ok()
+            .and_then(|result| supportsInterfaceCall::abi_decode_returns(&result, true).ok())
+            .is_some_and(|result| result._0)
+    };
+
+    query(ERC165_INTERFACE_ID) && !query(INVALID_INTERFACE_ID) && query(interface_id)
+}
+
+sol_storage! {
+    pub struct Erc165 {
+        /// Interface id to supported status
+        mapping(bytes4 => bool) supported_interfaces;
+    }
+}
+
+impl Erc165 {
+    /// Registers `interface_id` as supported. The invalid id `0xffffffff` is ignored.
+    /// Internal function without access restriction.
+    pub fn _register_interface(&mut self, interface_id: FixedBytes<4>) {
+        if interface_id == INVALID_INTERFACE_ID {
+            return;
+        }
+
+        self.supported_interfaces.insert(interface_id, true);
+    }
+}
+
+#[public]
+impl Erc165 {
+    /// Whether this contract implements `interface_id`, as defined in ERC-165.
+    pub fn supports_interface(&self, interface_id: FixedBytes<4>) -> bool {
+        interface_id == ERC165_INTERFACE_ID || self.supported_interfaces.get(interface_id)
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use alloc::vec;
+    use stylus_sdk::{
+        alloy_primitives::{address, U256},
+        alloy_sol_types::SolValue,
+        storage::StorageType,
+    };
+
+    const ACCOUNT: Address = address!("00000000000000000000000000000000000000e1");
+    const INTERFACE_ID: FixedBytes<4> = fixed_bytes!("7f5828d0");
+
+    /// Makes every contract answer `supportsInterface` with `supported(interface_id)`
+    fn contract(supported: impl Fn(FixedBytes<4>) -> bool + 'static) {
+        testing::on_call(move |_, calldata| {
+            let call = supportsInterfaceCall::abi_decode(calldata, true).unwrap();
+            Ok(supported(call.interface_id).abi_encode())
+        });
+    }
+
+    #[test]
+    fn detects_the_interfaces_of_a_contract() {
+        testing::reset();
+        contract(|id| id == ERC165_INTERFACE_ID || id == INTERFACE_ID);
+
+        assert!(supports_interface(ACCOUNT, INTERFACE_ID));
+        assert!(!supports_interface(ACCOUNT, fixed_bytes!("12345678")));
+    }
+
+    #[test]
+    fn contracts_must_follow_erc165() {
+        testing::reset();
+
+        // Without a call handler no account has code
+        assert!(!supports_interface(ACCOUNT, INTERFACE_ID));
+
+        // Not reporting ERC-165 itself
+        contract(|id| id == INTERFACE_ID);
+        assert!(!supports_interface(ACCOUNT, INTERFACE_ID));
+
+        // Reporting every interface, even the invalid one
+        contract(|_| true);
+        assert!(!supports_interface(ACCOUNT, INTERFACE_ID));
+
+        // Reverting or returning garbage
+        testing::on_call(|_, _| Err(vec![]));
+        assert!(!supports_interface(ACCOUNT, INTERFACE_ID));
+        testing::on_call(|_, _| Ok(vec![1]));
+        assert!(!supports_interface(ACCOUNT, INTERFACE_ID));
+    }
+
+    #[test]
+    fn reports_the_registered_interfaces() {
+        testing::reset();
+        let mut erc165 = unsafe { Erc165::new(U256::ZERO, 0) };
+        assert!(erc165.supports_interface(ERC165_INTERFACE_ID));
+        assert!(!erc165.supports_interface(INTERFACE_ID));
+
+        erc165._register_interface(INTERFACE_ID);
+        erc165._register_interface(INVALID_INTERFACE_ID);
+
+        assert!(erc165.supports_interface(INTERFACE_ID));
+        assert!(!erc165.supports_interface(INVALID_INTERFACE_ID));
+    }
+}
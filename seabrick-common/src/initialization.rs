@@ -0,0 +1,243 @@
+//! Initialization contract.
+//!
+//! Keeps the last initialized version, so each `reinitializer` version runs only
+//! once and versions can only increase. The first initialization is version 1.
+
+extern crate alloc;
+
+use stylus_sdk::{
+    alloy_primitives::{Address, U64},
+    alloy_sol_types::sol,
+    evm::log as log_event,
+    msg,
+    prelude::{public, sol_storage, SolidityError},
+};
+
+/// Account allowed to initialize the contract. It's set at build time through the
+/// `INITIALIZER_ADDRESS` environment variable, so nobody else can initialize a freshly
+/// deployed contract before us. If it's not set or is not an address, the wasm
+/// build fails. Host builds (eg. the ABI export) don't need it.
+#[cfg(not(any(test, feature = "testing")))]
+const INITIALIZER: Option<Address> = match option_env!("INITIALIZER_ADDRESS") {
+    Some(address) => Some(Address::new(parse_address(address))),
+    None => None,
+};
+
+/// A contract built without the initializer could never be initialized.
+#[cfg(all(target_arch = "wasm32", not(any(test, feature = "testing"))))]
+const _: () = assert!(
+    INITIALIZER.is_some(),
+    "INITIALIZER_ADDRESS must be set to build the contracts"
+);
+
+/// The unit tests initialize the contracts as the sender of the test host.
+#[cfg(any(test, feature = "testing"))]
+const INITIALIZER: Option<Address> = Some(Address::new(parse_address(
+    "0x00000000000000000000000000000000000000a1",
+)));
+
+sol! {
+    /// Emitted when contract is initialized or reinitialized to `version`
+    #[allow(missing_docs)]
+    event Initialized(uint64 version);
+}
+
+sol! {
+    /// The contract was already initialized to the requested version or a newer one
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error AlreadyInit();
+
+    /// The caller account is not allowed to initialize the contract
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error UnauthorizedInitializer(address account);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum InitializationError {
+    /// Contract already init
+    AlreadyInitialized(AlreadyInit),
+    /// Caller is not the initializer set at build time
+    UnauthorizedInitializer(UnauthorizedInitializer),
+}
+
+sol_storage! {
+    pub struct Initialization {
+        /// Last initialized version. It uses the slot of the former `is_init` bool,
+        /// so a contract initialized before versioning reads as version 1
+        uint64 initialized_version;
+    }
+}
+
+impl Initialization {
+    /// Checks that the contract can be initialized for the first time.
+    pub fn _check_init(&mut self) -> Result<(), InitializationError> {
+        self._check_reinit(1)
+    }
+
+    /// Checks that the contract can be reinitialized to `version`, which must be
+    /// newer than the current one.
+    pub fn _check_reinit(&mut self, version: u64) -> Result<(), InitializationError> {
+        if self.initialized_version.get() >= U64::from(version) {
+            return Err(InitializationError::AlreadyInitialized(AlreadyInit {}));
+        }
+
+        let account = msg::sender();
+        if Some(account) != initializer() {
+            return Err(InitializationError::UnauthorizedInitializer(
+                UnauthorizedInitializer { account },
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Sets the initialized version to `version`.
+    pub fn _set_initialized(&mut self, version: u64) {
+        self.initialized_version.set(U64::from(version));
+        log_event(Initialized { version });
+    }
+}
+
+#[public]
+impl Initialization {
+    /// Returns the last initialized version. Zero if not initialized yet.
+    pub fn initialized_version(&self) -> u64 {
+        self.initialized_version.get().to()
+    }
+}
+
+/// Returns the account allowed to initialize the contracts, set at build time.
+pub fn initializer() -> Option<Address> {
+    INITIALIZER
+}
+
+/// Parses a hex address, with or without the `0x` prefix. It's evaluated at build
+/// time, so a malformed address stops the build instead of making a contract that
+/// can never be initialized.
+const fn parse_address(address: &str) -> [u8; 20] {
+    let bytes = address.as_bytes();
+    let start = if bytes.len() >= 2 && bytes[0] == b'0' && (bytes[1] == b'x' || bytes[1] == b'X') {
+        2
+    } else {
+        0
+    };
+
+    if bytes.len() - start != 40 {
+        panic!("INITIALIZER_ADDRESS must be 20 bytes in hex");
+    }
+
+    let mut parsed = [0u8; 20];
+    let mut i = 0;
+    while i < 20 {
+        parsed[i] = hex_digit(bytes[start + 2 * i]) << 4 | hex_digit(bytes[start + 2 * i + 1]);
+        i += 1;
+    }
+
+    parsed
+}
+
+const fn hex_digit(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        b'A'..=b'F' => digit - b'A' + 10,
+        _ => panic!("INITIALIZER_ADDRESS must be 20 bytes in hex"),
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use alloc::vec::Vec;
+    use stylus_sdk::{
+        alloy_primitives::{address, U256},
+        alloy_sol_types::SolEvent,
+        storage::{StorageBool, StorageType},
+    };
+
+    fn initialization() -> Initialization {
+        testing::reset();
+        unsafe { Initialization::new(U256::ZERO, 0) }
+    }
+
+    #[test]
+    fn initializes_once() {
+        let mut init = initialization();
+        assert_eq!(init.initialized_version(), 0);
+
+        assert!(init._check_init().is_ok());
+        init._set_initialized(1);
+
+        assert_eq!(init.initialized_version(), 1);
+        assert!(matches!(
+            init._check_init(),
+            Err(InitializationError::AlreadyInitialized(_))
+        ));
+    }
+
+    #[test]
+    fn reinitializes_to_newer_versions_only() {
+        let mut init = initialization();
+        init._set_initialized(1);
+
+        assert!(init._check_reinit(3).is_ok());
+        init._set_initialized(3);
+
+        for version in [1, 2, 3] {
+            assert!(matches!(
+                init._check_reinit(version),
+                Err(InitializationError::AlreadyInitialized(_))
+            ));
+        }
+        assert!(init._check_reinit(4).is_ok());
+
+        let versions: Vec<u64> = testing::take_logs()
+            .iter()
+            .map(|log| Initialized::abi_decode_data(&log.data, true).unwrap().0)
+            .collect();
+        assert_eq!(versions, [1, 3]);
+    }
+
+    #[test]
+    fn former_init_flag_reads_as_version_one() {
+        let mut init = initialization();
+        // The former `bool is_init`, alone in the last byte of the slot
+        unsafe { StorageBool::new(U256::ZERO, 31) }.set(true);
+
+        assert_eq!(init.initialized_version(), 1);
+        assert!(matches!(
+            init._check_init(),
+            Err(InitializationError::AlreadyInitialized(_))
+        ));
+        assert!(init._check_reinit(2).is_ok());
+    }
+
+    #[test]
+    fn parses_addresses_with_and_without_prefix() {
+        let expected = address!("abcdef0123456789abcdef0123456789abcdef01");
+
+        assert_eq!(
+            parse_address("0xAbCdEf0123456789abcdef0123456789ABCDEF01"),
+            expected.0 .0
+        );
+        assert_eq!(
+            parse_address("abcdef0123456789abcdef0123456789abcdef01"),
+            expected.0 .0
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "20 bytes in hex")]
+    fn rejects_short_addresses() {
+        parse_address("0x1234");
+    }
+
+    #[test]
+    #[should_panic(expected = "20 bytes in hex")]
+    fn rejects_non_hex_digits() {
+        parse_address("0xabcdef0123456789abcdef0123456789abcdefzz");
+    }
+}
@@ -0,0 +1,17 @@
+#![cfg_attr(not(feature = "export-abi"), no_std)]
+
+//! Components shared by the Seabrick contracts.
+//!
+//! Each component is a storage struct that the contracts borrow and inherit,
+//! so a fix here applies to all of them.
+
+extern crate alloc;
+
+pub mod erc165;
+pub mod initialization;
+pub mod ownable;
+pub mod pausable;
+pub mod reentrancy_guard;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod timelock;
@@ -0,0 +1,326 @@
+//! Ownable contract.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::cell::Cell;
+use stylus_sdk::{
+    alloy_primitives::{fixed_bytes, Address, FixedBytes, U256},
+    alloy_sol_types::{sol, SolCall},
+    call::{static_call, CallContext, StaticCallContext},
+    evm, msg,
+    prelude::{public, sol_interface, sol_storage, SolidityError},
+    storage::StorageType,
+};
+
+use crate::erc165;
+
+sol_interface! {
+    interface Ownership {
+        function owner() external view returns (address);
+    }
+}
+
+sol! {
+    /// Call to the ownership contract that is not in the `sol_interface!` above,
+    /// which builds the selector of a `bytesN` argument as `bytes[N]`.
+    function hasRole(bytes32 role, address account) external view returns (bool);
+}
+
+/// ERC-173 (contract ownership standard) interface id. The ownership contract
+/// must report it through ERC-165 `supportsInterface`.
+pub const ERC173_INTERFACE_ID: FixedBytes<4> = fixed_bytes!("7f5828d0");
+
+/// Gas forwarded to the ownership contract on each call. Its views only read
+/// storage, so this is plenty, and a broken contract can't drain the caller.
+pub const OWNERSHIP_CALL_GAS: u64 = 100_000;
+
+/// Context for the view calls made to the ownership contract.
+///
+/// With the `reentrant` feature the SDK only builds static call contexts from the
+/// top level storage, which a component doesn't have. A static call can't modify
+/// any state, and the SDK flushes the storage cache before making it, so it's safe
+/// to make it from here.
+pub struct OwnershipCall;
+
+impl CallContext for OwnershipCall {
+    fn gas(&self) -> u64 {
+        OWNERSHIP_CALL_GAS
+    }
+}
+
+impl StaticCallContext for OwnershipCall {}
+
+/// Owner read from the ownership contract during the current call, so the access
+/// checks of a call ask for it once.
+///
+/// It's kept in memory and takes no storage slot, like the SDK storage cache, so
+/// each call starts without it and a transfer made in the ownership contract is
+/// seen by the next call. It's cleared when the ownership contract changes. A
+/// check made after an external call in the same call still sees the owner from
+/// before it, so the access checks come first.
+pub struct OwnerCache(Cell<Option<Address>>);
+
+impl StorageType for OwnerCache {
+    type Wraps<'a> = Self where Self: 'a;
+    type WrapsMut<'a> = Self where Self: 'a;
+
+    const REQUIRED_SLOTS: usize = 0;
+    const SLOT_BYTES: usize = 0;
+
+    unsafe fn new(_slot: U256, _offset: u8) -> Self {
+        Self(Cell::new(None))
+    }
+
+    fn load<'s>(self) -> Self::Wraps<'s>
+    where
+        Self: 's,
+    {
+        self
+    }
+
+    fn load_mut<'s>(self) -> Self::WrapsMut<'s>
+    where
+        Self: 's,
+    {
+        self
+    }
+}
+
+/// Roles that can be granted in the ownership contract
+pub mod roles {
+    use stylus_sdk::alloy_primitives::{fixed_bytes, FixedBytes};
+
+    /// keccak256("PRICE_ADMIN"). Can change the price and the price feeds.
+    pub const PRICE_ADMIN: FixedBytes<32> =
+        fixed_bytes!("48b9cb7d8da3e0a9da14d990c8cf59dc0f7704d452e793f8e7f0fb5d05e2096a");
+
+    /// keccak256("VAULT_ADMIN"). Can change where the funds are sent.
+    pub const VAULT_ADMIN: FixedBytes<32> =
+        fixed_bytes!("b15e2bb6de8e562b452dcfd0ca719f799c9919f6645dee389052d6326e77eb60");
+
+    /// keccak256("MINTER_ADMIN"). Can manage the minters and the burners.
+    pub const MINTER_ADMIN: FixedBytes<32> =
+        fixed_bytes!("498a9dae57f391d8efcc7bb3e7440ad6a25b1261044ef1b555c5484cb9f67659");
+
+    /// keccak256("PAUSER"). Can pause and unpause the contracts.
+    pub const PAUSER: FixedBytes<32> =
+        fixed_bytes!("539440820030c4994db4e31b6b800deafd503688728f932addfe7a410515c14c");
+}
+
+sol! {
+    /// Emitted when the ownership contract changes
+    #[allow(missing_docs)]
+    event OwnershipContractChanged(address indexed previous_contract, address indexed new_contract);
+}
+
+sol! {
+    /// The caller account is not authorized to perform an operation.
+    ///
+    /// * `account` - Account that was found to not be authorized.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error OwnableUnauthorizedAccount(address account);
+
+    /// The caller account does not have the role needed to perform an operation.
+    ///
+    /// * `account` - Account that was found to not be authorized.
+    /// * `role` - Role that was needed.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error OwnableMissingRole(address account, bytes32 role);
+
+    /// The ownership was renounced, so no owner operation can be performed anymore.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error OwnershipRenounced();
+
+    /// * `ownership_contract` - Address that's not allowed to become the ownership contract.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidOwnership(address ownership_contract);
+
+    /// The ownership contract could not be reached, or it does not implement ERC-173.
+    ///
+    /// * `ownership_contract` - Address of the unavailable ownership contract.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error OwnershipContractUnavailable(address ownership_contract);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum OwnableError {
+    /// The caller account is not authorized to perform an operation.
+    UnauthorizedAccount(OwnableUnauthorizedAccount),
+    /// The caller account does not have the needed role.
+    MissingRole(OwnableMissingRole),
+    /// The ownership was renounced.
+    OwnershipRenounced(OwnershipRenounced),
+    /// The ownership address is not a valid ownership contract
+    InvalidOwnership(InvalidOwnership),
+    /// The ownership contract could not be reached or is not ERC-173
+    OwnershipContractUnavailable(OwnershipContractUnavailable),
+}
+
+sol_storage! {
+    pub struct Ownable {
+        // Ownership contract address
+        address _ownership;
+        // Owner of the ownership contract, for the current call only
+        OwnerCache _owner_cache;
+    }
+}
+
+#[public]
+impl Ownable {
+    /// Returns the address of the current owner.
+    /// It's read from the ownership contract once per call, see [`OwnerCache`].
+    pub fn owner(&self) -> Result<Address, Vec<u8>> {
+        if let Some(owner) = self._owner_cache.0.get() {
+            return Ok(owner);
+        }
+
+        let ownership = self._ownership.get();
+        let owner_address = Ownership::new(ownership)
+            .owner(OwnershipCall)
+            .map_err(|_| unavailable(ownership))?;
+        self._owner_cache.0.set(Some(owner_address));
+        Ok(owner_address)
+    }
+
+    /// Returns the address of the ownership contract.
+    pub fn ownership_contract(&self) -> Address {
+        self._ownership.get()
+    }
+
+    pub fn change_ownership_contract(&mut self, new_address: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+
+        // The target must be an ownership contract, not an arbitrary address
+        check_ownership_contract(new_address)?;
+
+        // We check the owner on target contract to avoid losing the ownership
+        let target_owner_address = Ownership::new(new_address)
+            .owner(OwnershipCall)
+            .map_err(|_| unavailable(new_address))?;
+
+        // If target ownership address is not a contract, it will fail the transaction
+        // If target ownership address owner is not the same that the current one
+        //    we fail the transaction to avoid losing the ownership of the contracts
+        if target_owner_address != self.owner()? {
+            return Err(OwnableError::InvalidOwnership(InvalidOwnership {
+                ownership_contract: target_owner_address,
+            })
+            .into());
+        }
+
+        // Change the ownership contract address
+        self.store_ownership_contract(new_address);
+
+        Ok(())
+    }
+}
+
+impl Ownable {
+    /// Sets the ownership contract, after checking that it's a contract that
+    /// implements ERC-173 through ERC-165.
+    ///
+    /// # Errors
+    ///
+    /// If `address` is not a contract or does not report ERC-173, then the
+    /// error [`Error::OwnershipContractUnavailable`] is returned.
+    pub fn set_ownership_contract(&mut self, address: Address) -> Result<(), Vec<u8>> {
+        check_ownership_contract(address)?;
+        self.store_ownership_contract(address);
+
+        Ok(())
+    }
+
+    fn store_ownership_contract(&mut self, address: Address) {
+        let previous_contract = self._ownership.get();
+        self._ownership.set(address);
+        // The cached owner came from the previous contract
+        self._owner_cache.0.set(None);
+
+        evm::log(OwnershipContractChanged {
+            previous_contract,
+            new_contract: address,
+        });
+    }
+
+    /// Checks if the [`msg::sender`] is set as the owner. Calls made by the
+    /// ownership contract itself are treated as made by the owner: it only makes
+    /// calls in `executeBatch`, which only its owner can use, and in the multisig
+    /// executions once the signers confirmed them, while it's its own owner. An
+    /// ownership contract with any other way to make calls would let anyone use
+    /// it as the owner here.
+    ///
+    /// # Errors
+    ///
+    /// If the ownership was renounced, then the error [`Error::OwnershipRenounced`]
+    /// is returned.
+    /// If called by any account other than the owner, then the error
+    /// [`Error::UnauthorizedAccount`] is returned.
+    pub fn only_owner(&self) -> Result<(), Vec<u8>> {
+        let account = msg::sender();
+        let owner = self.owner()?;
+        if owner.is_zero() {
+            return Err(OwnableError::OwnershipRenounced(OwnershipRenounced {}).into());
+        }
+
+        if owner != account && self._ownership.get() != account {
+            return Err(
+                OwnableError::UnauthorizedAccount(OwnableUnauthorizedAccount { account }).into(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks if the [`msg::sender`] has `role` in the ownership contract.
+    /// The owner and the ownership contract itself are always allowed, for the
+    /// same reasons as in [`Self::only_owner`].
+    ///
+    /// # Errors
+    ///
+    /// If the ownership was renounced, then the error [`Error::OwnershipRenounced`]
+    /// is returned, since roles can no longer be managed.
+    /// If called by any account without the role, then the error
+    /// [`Error::MissingRole`] is returned.
+    pub fn only_role(&self, role: FixedBytes<32>) -> Result<(), Vec<u8>> {
+        let account = msg::sender();
+        let owner = self.owner()?;
+        if owner.is_zero() {
+            return Err(OwnableError::OwnershipRenounced(OwnershipRenounced {}).into());
+        }
+
+        let ownership = self._ownership.get();
+        if owner == account || ownership == account {
+            return Ok(());
+        }
+
+        let calldata = hasRoleCall { role, account }.abi_encode();
+        let has_role = static_call(OwnershipCall, ownership, &calldata)
+            .ok()
+            .and_then(|result| hasRoleCall::abi_decode_returns(&result, true).ok())
+            .ok_or_else(|| unavailable(ownership))?
+            ._0;
+        if !has_role {
+            return Err(OwnableError::MissingRole(OwnableMissingRole { account, role }).into());
+        }
+        Ok(())
+    }
+}
+
+/// Checks that `address` is a contract that implements ERC-173, detected through ERC-165.
+fn check_ownership_contract(address: Address) -> Result<(), Vec<u8>> {
+    if !erc165::supports_interface(address, ERC173_INTERFACE_ID) {
+        return Err(unavailable(address));
+    }
+
+    Ok(())
+}
+
+fn unavailable(ownership_contract: Address) -> Vec<u8> {
+    OwnableError::OwnershipContractUnavailable(OwnershipContractUnavailable { ownership_contract })
+        .into()
+}
@@ -0,0 +1,103 @@
+//! Pausable contract.
+//!
+//! Lets the contract stop some of its entrypoints in an emergency. Who can
+//! pause and unpause is decided by the contract using it.
+
+extern crate alloc;
+
+use stylus_sdk::{
+    alloy_sol_types::sol,
+    evm, msg,
+    prelude::{public, sol_storage, SolidityError},
+};
+
+sol! {
+    /// Emitted when the pause is triggered by `account`
+    #[allow(missing_docs)]
+    event Paused(address account);
+
+    /// Emitted when the pause is lifted by `account`
+    #[allow(missing_docs)]
+    event Unpaused(address account);
+}
+
+sol! {
+    /// The operation failed because the contract is paused.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error EnforcedPause();
+
+    /// The operation failed because the contract is not paused.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error ExpectedPause();
+}
+
+#[derive(SolidityError, Debug)]
+pub enum PausableError {
+    /// The contract is paused
+    EnforcedPause(EnforcedPause),
+    /// The contract is not paused
+    ExpectedPause(ExpectedPause),
+}
+
+sol_storage! {
+    pub struct Pausable {
+        /// Whether the contract is paused
+        bool paused;
+    }
+}
+
+impl Pausable {
+    /// Checks that the contract is not paused.
+    pub fn when_not_paused(&self) -> Result<(), PausableError> {
+        if self.paused.get() {
+            return Err(PausableError::EnforcedPause(EnforcedPause {}));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the contract is paused.
+    pub fn when_paused(&self) -> Result<(), PausableError> {
+        if !self.paused.get() {
+            return Err(PausableError::ExpectedPause(ExpectedPause {}));
+        }
+
+        Ok(())
+    }
+
+    /// Pauses the contract.
+    /// Internal function without access restriction.
+    pub fn _pause(&mut self) -> Result<(), PausableError> {
+        self.when_not_paused()?;
+        self.paused.set(true);
+
+        evm::log(Paused {
+            account: msg::sender(),
+        });
+
+        Ok(())
+    }
+
+    /// Unpauses the contract.
+    /// Internal function without access restriction.
+    pub fn _unpause(&mut self) -> Result<(), PausableError> {
+        self.when_paused()?;
+        self.paused.set(false);
+
+        evm::log(Unpaused {
+            account: msg::sender(),
+        });
+
+        Ok(())
+    }
+}
+
+#[public]
+impl Pausable {
+    /// Whether the contract is paused.
+    pub fn paused(&self) -> bool {
+        self.paused.get()
+    }
+}
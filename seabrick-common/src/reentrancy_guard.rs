@@ -0,0 +1,60 @@
+//! Reentrancy guard contract.
+//!
+//! A contract built without the `reentrant` feature, like the Market, already
+//! reverts when an external call calls back into it. Only ownership and the proxy
+//! are built with it. But the feature is easy to turn on by mistake (Cargo unifies
+//! it when the contracts are built together), and then any token or NFT call could
+//! reenter. So entrypoints that move value are wrapped with
+//! [`ReentrancyGuard::_enter`] and [`ReentrancyGuard::_exit`] anyway, and can't be
+//! reentered while they run whatever the build.
+
+extern crate alloc;
+
+use stylus_sdk::{
+    alloy_sol_types::sol,
+    prelude::{sol_storage, SolidityError},
+};
+
+sol! {
+    /// The guarded function was called while it was already running.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error ReentrantCall();
+}
+
+#[derive(SolidityError, Debug)]
+pub enum ReentrancyGuardError {
+    /// The guarded function was reentered
+    ReentrantCall(ReentrantCall),
+}
+
+sol_storage! {
+    pub struct ReentrancyGuard {
+        /// Whether a guarded function is running
+        bool entered;
+    }
+}
+
+impl ReentrancyGuard {
+    /// Marks a guarded function as running.
+    ///
+    /// # Errors
+    ///
+    /// If a guarded function is already running, then the error
+    /// [`ReentrantCall`] is returned.
+    pub fn _enter(&mut self) -> Result<(), ReentrancyGuardError> {
+        if self.entered.get() {
+            return Err(ReentrancyGuardError::ReentrantCall(ReentrantCall {}));
+        }
+
+        self.entered.set(true);
+
+        Ok(())
+    }
+
+    /// Marks the guarded function as finished. Must be called on every path
+    /// that doesn't revert, since a revert rolls back [`Self::_enter`] anyway.
+    pub fn _exit(&mut self) {
+        self.entered.set(false);
+    }
+}
@@ -0,0 +1,244 @@
+//! Host used to run the contracts in unit tests.
+//!
+//! It implements the `vm_hooks` the contracts import, so their code runs natively
+//! against an in-memory storage. Each test thread gets its own state, and [`reset`]
+//! clears it. Calls to other contracts are answered by the handler set with
+//! [`on_call`].
+//!
+//! The SDK caches the sender, the value, the timestamp and the contract address for
+//! the whole process, so they are fixed: see [`SENDER`], [`VALUE`], [`TIMESTAMP`]
+//! and [`CONTRACT`].
+
+extern crate std;
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{cell::RefCell, slice};
+use std::{collections::HashMap, thread_local};
+use stylus_sdk::alloy_primitives::{address, Address, Keccak256, U256};
+
+/// Account every call is made from
+pub const SENDER: Address = address!("00000000000000000000000000000000000000a1");
+
+/// Value in wei sent with every call
+pub const VALUE: U256 = U256::from_limbs([1_000, 0, 0, 0]);
+
+/// Address of the contract under test
+pub const CONTRACT: Address = address!("00000000000000000000000000000000000000c1");
+
+/// Block timestamp seen by the contracts
+pub const TIMESTAMP: u64 = 1_700_000_000;
+
+/// Handler for the calls to other contracts. It gets the target and the calldata,
+/// and returns the output or the revert data.
+pub type CallHandler = Box<dyn FnMut(Address, &[u8]) -> Result<Vec<u8>, Vec<u8>>>;
+
+/// A log emitted by the contract
+pub struct Log {
+    pub topics: Vec<[u8; 32]>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Default)]
+struct Host {
+    storage: HashMap<[u8; 32], [u8; 32]>,
+    logs: Vec<Log>,
+    return_data: Vec<u8>,
+    on_call: Option<CallHandler>,
+    /// Target and value of every call made
+    calls: Vec<(Address, U256)>,
+}
+
+thread_local! {
+    static HOST: RefCell<Host> = RefCell::new(Host::default());
+}
+
+/// Clears the storage, the logs and the call handler of the current thread
+pub fn reset() {
+    HOST.with(|host| *host.borrow_mut() = Host::default());
+}
+
+/// Sets the handler for the calls to other contracts
+pub fn on_call(handler: impl FnMut(Address, &[u8]) -> Result<Vec<u8>, Vec<u8>> + 'static) {
+    HOST.with(|host| host.borrow_mut().on_call = Some(Box::new(handler)));
+}
+
+/// Takes the logs emitted so far
+pub fn take_logs() -> Vec<Log> {
+    HOST.with(|host| core::mem::take(&mut host.borrow_mut().logs))
+}
+
+/// Takes the target and the value of the calls made so far
+pub fn take_calls() -> Vec<(Address, U256)> {
+    HOST.with(|host| core::mem::take(&mut host.borrow_mut().calls))
+}
+
+fn call(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    value: U256,
+    return_data_len: *mut usize,
+) -> u8 {
+    let contract = Address::from_slice(unsafe { slice::from_raw_parts(contract, 20) });
+    let calldata = unsafe { slice::from_raw_parts(calldata, calldata_len) };
+
+    HOST.with(|host| {
+        let mut host = host.borrow_mut();
+        host.calls.push((contract, value));
+        let mut handler = host
+            .on_call
+            .take()
+            .expect("unexpected call to another contract");
+        let result = handler(contract, calldata);
+        host.on_call = Some(handler);
+
+        let (status, data) = match result {
+            Ok(data) => (0, data),
+            Err(data) => (1, data),
+        };
+        unsafe { *return_data_len = data.len() };
+        host.return_data = data;
+        status
+    })
+}
+
+#[no_mangle]
+unsafe extern "C" fn storage_load_bytes32(key: *const u8, dest: *mut u8) {
+    let key: [u8; 32] = slice::from_raw_parts(key, 32).try_into().unwrap();
+    let value = HOST.with(|host| host.borrow().storage.get(&key).copied().unwrap_or_default());
+    slice::from_raw_parts_mut(dest, 32).copy_from_slice(&value);
+}
+
+#[no_mangle]
+unsafe extern "C" fn storage_cache_bytes32(key: *const u8, value: *const u8) {
+    let key: [u8; 32] = slice::from_raw_parts(key, 32).try_into().unwrap();
+    let value: [u8; 32] = slice::from_raw_parts(value, 32).try_into().unwrap();
+    HOST.with(|host| host.borrow_mut().storage.insert(key, value));
+}
+
+#[no_mangle]
+extern "C" fn storage_flush_cache(_clear: bool) {}
+
+#[no_mangle]
+unsafe extern "C" fn native_keccak256(bytes: *const u8, len: usize, output: *mut u8) {
+    let mut hasher = Keccak256::new();
+    hasher.update(slice::from_raw_parts(bytes, len));
+    slice::from_raw_parts_mut(output, 32).copy_from_slice(hasher.finalize().as_slice());
+}
+
+#[no_mangle]
+unsafe extern "C" fn emit_log(data: *const u8, len: usize, topics: usize) {
+    let data = slice::from_raw_parts(data, len);
+    let (topics_data, data) = data.split_at(topics * 32);
+    let topics = topics_data
+        .chunks(32)
+        .map(|topic| topic.try_into().unwrap())
+        .collect();
+
+    HOST.with(|host| {
+        host.borrow_mut().logs.push(Log {
+            topics,
+            data: data.to_vec(),
+        })
+    });
+}
+
+#[no_mangle]
+unsafe extern "C" fn msg_sender(sender: *mut u8) {
+    slice::from_raw_parts_mut(sender, 20).copy_from_slice(SENDER.as_slice());
+}
+
+#[no_mangle]
+unsafe extern "C" fn msg_value(value: *mut u8) {
+    slice::from_raw_parts_mut(value, 32).copy_from_slice(&VALUE.to_be_bytes::<32>());
+}
+
+#[no_mangle]
+extern "C" fn msg_reentrant() -> bool {
+    false
+}
+
+#[no_mangle]
+unsafe extern "C" fn contract_address(address: *mut u8) {
+    slice::from_raw_parts_mut(address, 20).copy_from_slice(CONTRACT.as_slice());
+}
+
+#[no_mangle]
+extern "C" fn block_timestamp() -> u64 {
+    TIMESTAMP
+}
+
+#[no_mangle]
+extern "C" fn evm_gas_left() -> u64 {
+    u64::MAX
+}
+
+/// Every account with a call handler set is treated as a contract
+#[no_mangle]
+unsafe extern "C" fn account_codehash(_address: *const u8, dest: *mut u8) {
+    let has_handler = HOST.with(|host| host.borrow().on_call.is_some());
+    slice::from_raw_parts_mut(dest, 32).fill(has_handler as u8);
+}
+
+#[no_mangle]
+unsafe extern "C" fn call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    value: *const u8,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    let value = U256::from_be_slice(slice::from_raw_parts(value, 32));
+    call(contract, calldata, calldata_len, value, return_data_len)
+}
+
+#[no_mangle]
+unsafe extern "C" fn delegate_call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    call(
+        contract,
+        calldata,
+        calldata_len,
+        U256::ZERO,
+        return_data_len,
+    )
+}
+
+#[no_mangle]
+unsafe extern "C" fn static_call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    call(
+        contract,
+        calldata,
+        calldata_len,
+        U256::ZERO,
+        return_data_len,
+    )
+}
+
+#[no_mangle]
+unsafe extern "C" fn read_return_data(dest: *mut u8, offset: usize, size: usize) -> usize {
+    HOST.with(|host| {
+        let host = host.borrow();
+        let data = host.return_data.get(offset..).unwrap_or_default();
+        let len = data.len().min(size);
+        slice::from_raw_parts_mut(dest, len).copy_from_slice(&data[..len]);
+        len
+    })
+}
+
+#[no_mangle]
+extern "C" fn return_data_size() -> usize {
+    HOST.with(|host| host.borrow().return_data.len())
+}
@@ -0,0 +1,329 @@
+//! Timelock contract.
+//!
+//! Owner calls are first scheduled with an ETA, and can only be executed once
+//! that ETA is reached and before [`GRACE_PERIOD`] has passed since then. A
+//! scheduled call is identified by the keccak256 hash of its calldata.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{FixedBytes, U256},
+    alloy_sol_types::sol,
+    block,
+    crypto::keccak,
+    evm,
+    prelude::{public, sol_storage, SolidityError},
+};
+
+/// Time (in seconds) after its ETA during which a scheduled call can be executed.
+/// Once it passes, the call has to be scheduled again. Fourteen days.
+pub const GRACE_PERIOD: u64 = 14 * 24 * 60 * 60;
+
+sol! {
+    /// Emitted when a call is scheduled to be executed at `eta`
+    #[allow(missing_docs)]
+    event CallScheduled(bytes32 indexed id, bytes data, uint256 eta);
+
+    /// Emitted when a scheduled call is executed
+    #[allow(missing_docs)]
+    event CallExecuted(bytes32 indexed id, bytes data);
+
+    /// Emitted when a scheduled call is cancelled
+    #[allow(missing_docs)]
+    event CallCancelled(bytes32 indexed id);
+
+    /// Emitted when the minimum delay changes
+    #[allow(missing_docs)]
+    event MinDelayChanged(uint256 previous_delay, uint256 new_delay);
+}
+
+sol! {
+    /// The ETA is before the minimum delay.
+    ///
+    /// * `eta` - The requested ETA.
+    /// * `min_eta` - The earliest ETA allowed.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error TimelockInsufficientDelay(uint256 eta, uint256 min_eta);
+
+    /// The call is already scheduled.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error TimelockAlreadyScheduled(bytes32 id);
+
+    /// The call is not scheduled.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error TimelockNotScheduled(bytes32 id);
+
+    /// The call is scheduled but its ETA has not been reached.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error TimelockNotReady(bytes32 id, uint256 eta);
+
+    /// The call grace period has passed, so it has to be scheduled again.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error TimelockExpired(bytes32 id, uint256 expired_at);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum TimelockError {
+    /// The ETA is before the minimum delay.
+    InsufficientDelay(TimelockInsufficientDelay),
+    /// The call is already scheduled.
+    AlreadyScheduled(TimelockAlreadyScheduled),
+    /// The call is not scheduled.
+    NotScheduled(TimelockNotScheduled),
+    /// The call ETA has not been reached.
+    NotReady(TimelockNotReady),
+    /// The call grace period has passed.
+    Expired(TimelockExpired),
+}
+
+sol_storage! {
+    pub struct Timelock {
+        /// Minimum time (in seconds) between scheduling and executing a call
+        uint256 min_delay;
+        /// Call id to the timestamp from which it can be executed
+        mapping(bytes32 => uint256) etas;
+    }
+}
+
+impl Timelock {
+    /// Schedules the call `data` to be executed from `eta`. A call whose grace
+    /// period has passed can be scheduled again.
+    /// Internal function without access restriction.
+    pub fn _schedule(&mut self, data: &[u8], eta: U256) -> Result<FixedBytes<32>, TimelockError> {
+        let id = keccak(data);
+
+        let scheduled = self.etas.get(id);
+        if !scheduled.is_zero() && U256::from(block::timestamp()) <= expiry(scheduled) {
+            return Err(TimelockError::AlreadyScheduled(TimelockAlreadyScheduled {
+                id,
+            }));
+        }
+
+        let min_eta = U256::from(block::timestamp()) + self.min_delay.get();
+        if eta < min_eta {
+            return Err(TimelockError::InsufficientDelay(
+                TimelockInsufficientDelay { eta, min_eta },
+            ));
+        }
+
+        self.etas.setter(id).set(eta);
+
+        evm::log(CallScheduled {
+            id,
+            data: data.to_vec().into(),
+            eta,
+        });
+
+        Ok(id)
+    }
+
+    /// Cancels the scheduled call `id`.
+    /// Internal function without access restriction.
+    pub fn _cancel(&mut self, id: FixedBytes<32>) -> Result<(), TimelockError> {
+        if self.etas.get(id).is_zero() {
+            return Err(TimelockError::NotScheduled(TimelockNotScheduled { id }));
+        }
+
+        self.etas.delete(id);
+
+        evm::log(CallCancelled { id });
+
+        Ok(())
+    }
+
+    /// Checks that the call `data` was scheduled, its ETA was reached and its
+    /// grace period has not passed, and marks it as executed. Must be called by
+    /// the function being executed with its own calldata.
+    pub fn _consume(&mut self, data: &[u8]) -> Result<(), TimelockError> {
+        let id = keccak(data);
+        let eta = self.etas.get(id);
+
+        if eta.is_zero() {
+            return Err(TimelockError::NotScheduled(TimelockNotScheduled { id }));
+        }
+
+        let now = U256::from(block::timestamp());
+        if now < eta {
+            return Err(TimelockError::NotReady(TimelockNotReady { id, eta }));
+        }
+
+        let expired_at = expiry(eta);
+        if now > expired_at {
+            return Err(TimelockError::Expired(TimelockExpired { id, expired_at }));
+        }
+
+        self.etas.delete(id);
+
+        evm::log(CallExecuted {
+            id,
+            data: data.to_vec().into(),
+        });
+
+        Ok(())
+    }
+
+    /// Sets the minimum delay for new scheduled calls.
+    /// Internal function without access restriction.
+    pub fn _set_min_delay(&mut self, delay: U256) {
+        let previous_delay = self.min_delay.get();
+        self.min_delay.set(delay);
+
+        evm::log(MinDelayChanged {
+            previous_delay,
+            new_delay: delay,
+        });
+    }
+}
+
+/// Last timestamp at which a call scheduled for `eta` can be executed
+fn expiry(eta: U256) -> U256 {
+    eta.saturating_add(U256::from(GRACE_PERIOD))
+}
+
+#[public]
+impl Timelock {
+    /// Returns the minimum delay (in seconds) for scheduled calls.
+    pub fn min_delay(&self) -> Result<U256, Vec<u8>> {
+        Ok(self.min_delay.get())
+    }
+
+    /// Returns the ETA of the scheduled call `id`, or zero if it's not scheduled.
+    /// The call can be executed until [`GRACE_PERIOD`] after it.
+    pub fn get_eta(&self, id: FixedBytes<32>) -> Result<U256, Vec<u8>> {
+        Ok(self.etas.get(id))
+    }
+
+    /// Returns the id used to schedule the call `data`.
+    pub fn hash_call(&self, data: Bytes) -> Result<FixedBytes<32>, Vec<u8>> {
+        Ok(keccak(data))
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TIMESTAMP};
+    use stylus_sdk::storage::StorageType;
+
+    const DATA: &[u8] = b"call";
+
+    fn timelock(min_delay: u64) -> Timelock {
+        testing::reset();
+        let mut timelock = unsafe { Timelock::new(U256::ZERO, 0) };
+        timelock._set_min_delay(U256::from(min_delay));
+        timelock
+    }
+
+    /// Schedules `DATA` as if it was scheduled in the past, to be executed at `eta`
+    fn schedule_at(timelock: &mut Timelock, eta: u64) {
+        timelock.etas.setter(keccak(DATA)).set(U256::from(eta));
+    }
+
+    #[test]
+    fn eta_must_respect_the_min_delay() {
+        let mut timelock = timelock(60);
+
+        assert!(matches!(
+            timelock._schedule(DATA, U256::from(TIMESTAMP + 59)),
+            Err(TimelockError::InsufficientDelay(_))
+        ));
+
+        let id = timelock._schedule(DATA, U256::from(TIMESTAMP + 60));
+        assert_eq!(id.ok(), Some(keccak(DATA)));
+        assert_eq!(
+            timelock.get_eta(keccak(DATA)),
+            Ok(U256::from(TIMESTAMP + 60))
+        );
+    }
+
+    #[test]
+    fn call_is_scheduled_once() {
+        let mut timelock = timelock(0);
+        assert!(timelock._schedule(DATA, U256::from(TIMESTAMP)).is_ok());
+
+        assert!(matches!(
+            timelock._schedule(DATA, U256::from(TIMESTAMP + 1)),
+            Err(TimelockError::AlreadyScheduled(_))
+        ));
+    }
+
+    #[test]
+    fn call_runs_once_from_its_eta() {
+        let mut timelock = timelock(0);
+        assert!(matches!(
+            timelock._consume(DATA),
+            Err(TimelockError::NotScheduled(_))
+        ));
+
+        assert!(timelock._schedule(DATA, U256::from(TIMESTAMP + 1)).is_ok());
+        assert!(matches!(
+            timelock._consume(DATA),
+            Err(TimelockError::NotReady(_))
+        ));
+
+        schedule_at(&mut timelock, TIMESTAMP);
+        assert!(timelock._consume(DATA).is_ok());
+        assert!(matches!(
+            timelock._consume(DATA),
+            Err(TimelockError::NotScheduled(_))
+        ));
+    }
+
+    #[test]
+    fn cancelled_call_cannot_run() {
+        let mut timelock = timelock(0);
+        assert!(matches!(
+            timelock._cancel(keccak(DATA)),
+            Err(TimelockError::NotScheduled(_))
+        ));
+
+        assert!(timelock._schedule(DATA, U256::from(TIMESTAMP)).is_ok());
+        assert!(timelock._cancel(keccak(DATA)).is_ok());
+
+        assert_eq!(timelock.get_eta(keccak(DATA)), Ok(U256::ZERO));
+        assert!(matches!(
+            timelock._consume(DATA),
+            Err(TimelockError::NotScheduled(_))
+        ));
+    }
+
+    #[test]
+    fn call_can_be_executed_until_the_end_of_the_grace_period() {
+        let mut timelock = timelock(0);
+        schedule_at(&mut timelock, TIMESTAMP - GRACE_PERIOD);
+
+        assert!(timelock._consume(DATA).is_ok());
+    }
+
+    #[test]
+    fn call_expires_after_the_grace_period() {
+        let mut timelock = timelock(0);
+        schedule_at(&mut timelock, TIMESTAMP - GRACE_PERIOD - 1);
+
+        assert!(matches!(
+            timelock._consume(DATA),
+            Err(TimelockError::Expired(_))
+        ));
+    }
+
+    #[test]
+    fn expired_call_can_be_scheduled_again() {
+        let mut timelock = timelock(0);
+        schedule_at(&mut timelock, TIMESTAMP - GRACE_PERIOD);
+        assert!(matches!(
+            timelock._schedule(DATA, U256::from(TIMESTAMP)),
+            Err(TimelockError::AlreadyScheduled(_))
+        ));
+
+        schedule_at(&mut timelock, TIMESTAMP - GRACE_PERIOD - 1);
+        assert!(timelock._schedule(DATA, U256::from(TIMESTAMP)).is_ok());
+        assert!(timelock._consume(DATA).is_ok());
+    }
+}
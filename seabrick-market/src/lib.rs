@@ -1,29 +1,43 @@
-#![cfg_attr(not(feature = "export-abi"), no_std, no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(feature = "export-abi"), no_std)]
 
 extern crate alloc;
-mod initialization;
-mod ownable;
 
 use alloc::{vec, vec::Vec};
-use initialization::Initialization;
-use ownable::Ownable;
+use seabrick_common::{
+    erc165::Erc165,
+    initialization::Initialization,
+    ownable::{
+        roles::{PAUSER, PRICE_ADMIN, VAULT_ADMIN},
+        Ownable,
+    },
+    pausable::Pausable,
+    reentrancy_guard::ReentrancyGuard,
+    timelock::Timelock,
+};
 use stylus_sdk::{
-    alloy_primitives::{Address, FixedBytes, U256},
-    alloy_sol_types::sol,
+    abi::Bytes,
+    alloy_primitives::{Address, FixedBytes, U16, U256, U8},
+    alloy_sol_types::{sol, SolCall},
+    block,
     call::Call,
-    evm,
+    contract,
+    crypto::keccak,
+    evm, msg,
     prelude::{entrypoint, public, sol_interface, sol_storage, SolidityError},
 };
 
 sol_interface! {
     interface ISeabrick {
         function mint(address to) external returns (uint256);
-        function mintBatch(address to, uint8 amount) external;
+        function mintBatch(address to, uint32 amount) external;
         function totalSupply() external returns (uint256);
+        function burnFrom(address from, uint256 token_id) external;
     }
 
     interface IERC20 {
         function decimals() external view returns (uint8);
+        function balanceOf(address account) external view returns (uint256);
         function transfer(address to, uint256 value) external returns (bool);
         function transferFrom(address from, address to, uint256 value) external returns (bool);
     }
@@ -40,20 +54,96 @@ sol! {
     /// A chainlink aggregator added
     event AggregatorAdded(bytes32 name, address aggregator, address token);
 
+    /// A fixed rate feed added. `rate` is the USD value of one token, with 8 decimals
+    event FixedRateAdded(bytes32 name, address token, uint256 rate);
+
     /// Emitted when contract sell a NFT
     event Buy(address buyer, uint256 id, uint256 amountSpent, bytes32 aggregator);
 
-    /// Tokens Collected and sent to vault address
+    /// Tokens Collected and sent to a payee. Emitted once per payee on each buy
     event Collected(address token, uint256 amount, bytes32 aggregator, address vault);
 
     // Initial sale detail
-    event SaleDetails(address nftAddress, address ownershipContract, address claimVault, uint256 price);
+    event SaleDetails(address nftAddress, address ownershipContract, uint256 price);
 
     // New price added
     event PriceAdded(uint256 newPrice);
 
-    // New Claim address added
-    event ClaimVaultAdded(address newClaimVault);
+    /// Emitted when the payees or their shares (in basis points) change
+    event PayeesUpdated(address[] payees, uint16[] shares);
+
+    /// Emitted when the escrow mode is turned on or off
+    event EscrowModeChanged(bool enabled);
+
+    /// Tokens collected and kept in the market for a payee to withdraw.
+    /// Emitted instead of `Collected` when the escrow mode is on, and with a zero
+    /// aggregator when the funds of a successful campaign are released
+    event Escrowed(address token, uint256 amount, bytes32 aggregator, address payee);
+
+    /// Emitted when a payee withdraws its escrowed tokens
+    event Withdrawn(address token, address payee, uint256 amount);
+
+    /// Emitted when a campaign starts. `softCap` is in USD, like the price
+    event CampaignStarted(uint256 softCap, uint256 deadline);
+
+    /// Tokens received from `buyer` for `nfts` NFTs while the campaign is open
+    event CampaignContribution(address buyer, address token, uint256 amount, uint256 nfts, bytes32 aggregator);
+
+    /// Emitted when the campaign ends. `raised` is in USD, like the price
+    event CampaignFinalized(bool succeeded, uint256 raised);
+
+    /// NFTs minted to `buyer` after a successful campaign, starting from `firstId`
+    event CampaignClaimed(address buyer, uint256 firstId, uint256 amount);
+
+    /// Tokens given back to `buyer` after a failed campaign
+    event CampaignRefunded(address buyer, address token, uint256 amount);
+
+    /// Emitted when the cooling-off period changes. Zero means there is none
+    event CoolingOffPeriodChanged(uint256 period);
+
+    /// `buyer` returned `tokenId` within the cooling-off period and got back `amount` of `token`
+    event Refunded(address buyer, uint256 tokenId, address token, uint256 amount);
+}
+
+sol! {
+    /// Sale interface of the market, reported through ERC-165
+    interface ISeabrickMarket {
+        function buy(address buyer, bytes32 name, uint8 amount) external;
+        function pause() external;
+        function unpause() external;
+    }
+}
+
+/// ERC-165 id of [`ISeabrickMarket`], the XOR of its selectors (`0x3725fe31`).
+pub const ISEABRICK_MARKET_INTERFACE_ID: FixedBytes<4> = FixedBytes(xor_selectors(&[
+    ISeabrickMarket::buyCall::SELECTOR,
+    ISeabrickMarket::pauseCall::SELECTOR,
+    ISeabrickMarket::unpauseCall::SELECTOR,
+]));
+
+const fn xor_selectors(selectors: &[[u8; 4]]) -> [u8; 4] {
+    let mut id = [0u8; 4];
+    let mut i = 0;
+    while i < selectors.len() {
+        let mut j = 0;
+        while j < 4 {
+            id[j] ^= selectors[i][j];
+            j += 1;
+        }
+        i += 1;
+    }
+    id
+}
+
+sol! {
+    /// Timelocked calls. Used to build the calldata that must be scheduled
+    function setAgregators(bytes32[] names, address[] agregators, address[] tokens);
+    function setPrice(uint256 price);
+    function setPayees(address[] payees, uint16[] shares);
+    function setMinDelay(uint256 delay);
+    function setCoolingOffPeriod(uint256 period);
+    function setFixedRate(bytes32 name, address token, uint256 rate);
+    function startCampaign(uint256 softCap, uint256 deadline);
 }
 
 sol! {
@@ -66,20 +156,92 @@ sol! {
     /// Error when setting price as zero
     error ZeroPrice();
 
+    /// Error when setting a fixed rate as zero
+    error ZeroRate();
+
     /// Error when setting Claim Vault Zero Address
     error VaultZeroAddress();
 
     /// Error when amount to Buy is zero
     error ZeroBuyAmount();
+
+    /// Mismatch on payees and shares provided
+    error MismatchPayees();
+
+    /// The payee shares don't add up to 10000 basis points
+    error InvalidShares(uint256 total);
+
+    /// The account has no escrowed tokens to withdraw
+    error NothingToWithdraw(address token, address payee);
+
+    /// The soft cap is zero or the deadline is not in the future
+    error InvalidCampaign();
+
+    /// The operation is not allowed in the current campaign state
+    error InvalidCampaignState(uint8 state);
+
+    /// The campaign deadline has passed, so no more contributions are taken
+    error CampaignEnded();
+
+    /// The campaign can't be finalized before its deadline, unless the soft cap is met
+    error CampaignNotEnded();
+
+    /// The account has no NFTs to claim from the campaign
+    error NothingToClaim(address buyer);
+
+    /// The account has no tokens to get back from the campaign
+    error NothingToRefund(address buyer);
+
+    /// The token was not bought with a cooling-off period, or it was already refunded or released
+    error UnknownPurchase(uint256 tokenId);
+
+    /// The token can't be returned: the caller is not its buyer or the cooling-off period is over
+    error NotRefundable(uint256 tokenId);
+
+    /// The payment can't be released to the payees until the cooling-off period is over
+    error StillRefundable(uint256 tokenId);
 }
 
+/// Basis points that the payee shares must add up to (100%).
+pub const TOTAL_SHARES: u32 = 10_000;
+
+/// Decimals of the fixed rates, the same as the chainlink USD feeds.
+/// A stablecoin worth 1 USD has a rate of `100_000_000`.
+pub const FIXED_RATE_DECIMALS: u8 = 8;
+
+/// No campaign was started, the sales work as usual.
+pub const CAMPAIGN_NONE: u8 = 0;
+/// The campaign is taking contributions.
+pub const CAMPAIGN_OPEN: u8 = 1;
+/// The soft cap was met. The NFTs can be claimed and the sales work as usual.
+pub const CAMPAIGN_SUCCEEDED: u8 = 2;
+/// The soft cap was not met. The buyers can get their tokens back and the sales are closed.
+pub const CAMPAIGN_FAILED: u8 = 3;
+
+/// Most NFTs minted by a single [`Market::claim`], the batch limit of the Seabrick
+/// `mintBatch`. Larger claims are minted over several calls.
+pub const MAX_CLAIM_BATCH: u32 = 250;
+
 #[derive(SolidityError)]
 pub enum MarketError {
     PaymentFailed(PaymentFailed),
     MismatchAggregators(MismatchAggregators),
     ZeroPrice(ZeroPrice),
+    ZeroRate(ZeroRate),
     VaultZeroAddress(VaultZeroAddress),
     ZeroBuyAmount(ZeroBuyAmount),
+    MismatchPayees(MismatchPayees),
+    InvalidShares(InvalidShares),
+    NothingToWithdraw(NothingToWithdraw),
+    InvalidCampaign(InvalidCampaign),
+    InvalidCampaignState(InvalidCampaignState),
+    CampaignEnded(CampaignEnded),
+    CampaignNotEnded(CampaignNotEnded),
+    NothingToClaim(NothingToClaim),
+    NothingToRefund(NothingToRefund),
+    UnknownPurchase(UnknownPurchase),
+    NotRefundable(NotRefundable),
+    StillRefundable(StillRefundable),
 }
 
 sol_storage! {
@@ -91,6 +253,29 @@ sol_storage! {
         /// If token address is a non-zero address, it will try to be used to make transfers.
         /// If this token address is a zero address, it will assume that is native currency (like ETH in Arbitrum One)
         address token;
+
+        /// Whether the token has a fixed USD value, so no oracle is called for it
+        bool fixed_rate;
+
+        /// USD value of one token with `FIXED_RATE_DECIMALS` decimals, used when `fixed_rate` is set
+        uint256 rate;
+    }
+
+    pub struct PurchaseInfo {
+        /// Account that paid for the token
+        address buyer;
+
+        /// Token used to pay
+        address token;
+
+        /// Amount paid, held by the market until it's refunded or released
+        uint256 amount;
+
+        /// Price feed used for the payment
+        bytes32 aggregator;
+
+        /// Timestamp until which the token can be returned
+        uint256 refundable_until;
     }
 
     #[entrypoint]
@@ -101,7 +286,7 @@ sol_storage! {
         /// Contract NFT address
         address nft_token;
 
-        /// Vault address where the tokens claimed will go to
+        /// Former single vault, replaced by `payees`. Kept so the storage layout doesn't move
         address claim_vault;
 
         /// Mapping for hashed names. Example keccak("ETH/USD") to his price feed aggregator address.
@@ -114,32 +299,125 @@ sol_storage! {
 
         #[borrow]
         Ownable ownable;
+
+        #[borrow]
+        Timelock timelock;
+
+        #[borrow]
+        Pausable pausable;
+
+        ReentrancyGuard reentrancy_guard;
+
+        #[borrow]
+        Erc165 erc165;
+
+        /// Accounts the sale proceeds are split between
+        address[] payees;
+
+        /// Share of each payee in basis points, in the same order as `payees`
+        uint16[] payee_shares;
+
+        /// Whether the sale proceeds are kept in the market for the payees to
+        /// withdraw, instead of being sent to them on each buy
+        bool escrow_mode;
+
+        /// Escrowed tokens. Token address to payee to amount pending to withdraw
+        mapping(address => mapping(address => uint256)) pending_balances;
+
+        /// Total escrowed per token, the sum of its `pending_balances`
+        mapping(address => uint256) escrowed;
+
+        /// Campaign state, one of the `CAMPAIGN_*` values
+        uint8 campaign_state;
+
+        /// USD to raise (in the same units as `price`) for the campaign to succeed
+        uint256 campaign_soft_cap;
+
+        /// Timestamp from which the campaign takes no more contributions
+        uint256 campaign_deadline;
+
+        /// USD raised so far, counted at `price` per NFT
+        uint256 campaign_raised;
+
+        /// Tokens paid during the campaign
+        address[] campaign_tokens;
+
+        /// Token address to the amount held for the campaign
+        mapping(address => uint256) campaign_totals;
+
+        /// Buyer to token address to the amount paid during the campaign
+        mapping(address => mapping(address => uint256)) campaign_contributions;
+
+        /// Buyer to the NFTs it paid for during the campaign
+        mapping(address => uint256) campaign_nfts;
+
+        /// Seconds after a buy during which the buyer can return the token for a
+        /// full refund. Zero means there is no cooling-off period
+        uint256 cooling_off_period;
+
+        /// Token id to its payment, while it can still be refunded or released
+        mapping(uint256 => PurchaseInfo) purchases;
     }
 }
 
 impl Market {
     pub fn get_amount_price(&mut self, amount: u8, name: FixedBytes<32>) -> Result<U256, Vec<u8>> {
-        let payment_token = IERC20::new(self.price_feeds.get(name).token.get());
-        let oracle = AggregatorV3Interface::new(self.price_feeds.get(name).agregator_address.get());
-
-        // Get latest answer price
-        let latest_answer =
-            U256::from_limbs(oracle.latest_round_data(Call::new_in(self))?.1.into_limbs());
-
-        let oracle_decimals = U256::from(oracle.decimals(Call::new_in(self))?);
-
-        let payment_decimals = U256::from(payment_token.decimals(Call::new_in(self))?);
+        let (latest_answer, scale) = self.usd_rate(name)?;
 
         // Scaled price
-        let usd_price = self.price.get()
-            * U256::from(10).pow(payment_decimals)
-            * U256::from(10).pow(oracle_decimals);
+        let usd_price = self.price.get() * scale;
 
         let amount_need = usd_price.div_ceil(latest_answer);
 
         Ok(amount_need * U256::from(amount))
     }
 
+    /// Returns the USD value of the token of the `name` price feed, and the scale
+    /// it's given in: `10 ^ (token decimals + feed decimals)`. The USD value (in
+    /// the units of the price) of an amount of the token is `amount * rate / scale`.
+    fn usd_rate(&mut self, name: FixedBytes<32>) -> Result<(U256, U256), Vec<u8>> {
+        let payment_token = IERC20::new(self.price_feeds.get(name).token.get());
+
+        // Get latest answer price. Fixed rate tokens don't depend on an oracle
+        let (latest_answer, oracle_decimals) = if self.price_feeds.get(name).fixed_rate.get() {
+            (
+                self.price_feeds.get(name).rate.get(),
+                U256::from(FIXED_RATE_DECIMALS),
+            )
+        } else {
+            let oracle =
+                AggregatorV3Interface::new(self.price_feeds.get(name).agregator_address.get());
+            (
+                U256::from_limbs(oracle.latest_round_data(Call::new_in(self))?.1.into_limbs()),
+                U256::from(oracle.decimals(Call::new_in(self))?),
+            )
+        };
+
+        let payment_decimals = U256::from(payment_token.decimals(Call::new_in(self))?);
+
+        Ok((
+            latest_answer,
+            U256::from(10).pow(payment_decimals + oracle_decimals),
+        ))
+    }
+
+    /// Checks that the caller can schedule or cancel the call `data`,
+    /// which requires the same role needed to execute it
+    pub fn only_scheduler(&self, data: &[u8]) -> Result<(), Vec<u8>> {
+        let selector: [u8; 4] = data
+            .get(..4)
+            .and_then(|selector| selector.try_into().ok())
+            .unwrap_or_default();
+
+        match selector {
+            setAgregatorsCall::SELECTOR | setPriceCall::SELECTOR | setFixedRateCall::SELECTOR => {
+                self.ownable.only_role(PRICE_ADMIN)
+            }
+            setPayeesCall::SELECTOR => self.ownable.only_role(VAULT_ADMIN),
+            _ => self.ownable.only_owner(),
+        }
+    }
+
     pub fn set_aggregators_internal(
         &mut self,
         names: Vec<FixedBytes<32>>,
@@ -155,6 +433,8 @@ impl Market {
             let mut map_aggregator = self.price_feeds.setter(names[i]);
             map_aggregator.agregator_address.set(agregators[i]);
             map_aggregator.token.set(tokens[i]);
+            map_aggregator.fixed_rate.set(false);
+            map_aggregator.rate.set(U256::ZERO);
 
             evm::log(AggregatorAdded {
                 name: names[i],
@@ -165,17 +445,342 @@ impl Market {
 
         Ok(())
     }
+
+    /// Replaces the payees and their shares. Shares are in basis points and must
+    /// add up to [`TOTAL_SHARES`].
+    pub fn set_payees_internal(
+        &mut self,
+        payees: Vec<Address>,
+        shares: Vec<u16>,
+    ) -> Result<(), Vec<u8>> {
+        if payees.len() != shares.len() {
+            return Err(MarketError::MismatchPayees(MismatchPayees {}).into());
+        }
+
+        if payees.iter().any(|payee| payee.is_zero()) {
+            return Err(MarketError::VaultZeroAddress(VaultZeroAddress {}).into());
+        }
+
+        let total = shares.iter().map(|share| u32::from(*share)).sum::<u32>();
+        if total != TOTAL_SHARES {
+            return Err(MarketError::InvalidShares(InvalidShares {
+                total: U256::from(total),
+            })
+            .into());
+        }
+
+        while self.payees.pop().is_some() {}
+        while self.payee_shares.pop().is_some() {}
+
+        for (payee, share) in payees.iter().zip(shares.iter()) {
+            self.payees.push(*payee);
+            self.payee_shares.push(U16::from(*share));
+        }
+
+        evm::log(PayeesUpdated { payees, shares });
+
+        Ok(())
+    }
+
+    /// Returns the payees and their shares, in basis points
+    fn payees_list(&self) -> (Vec<Address>, Vec<u16>) {
+        let mut payees = Vec::with_capacity(self.payees.len());
+        let mut shares = Vec::with_capacity(self.payees.len());
+        for i in 0..self.payees.len() {
+            if let (Some(payee), Some(share)) = (self.payees.get(i), self.payee_shares.get(i)) {
+                payees.push(payee);
+                shares.push(share.to());
+            }
+        }
+
+        (payees, shares)
+    }
+
+    /// Sells `amount` NFTs to `buyer`. See [`Market::buy`].
+    fn buy_internal(
+        &mut self,
+        buyer: Address,
+        name: FixedBytes<32>,
+        amount: u8,
+    ) -> Result<(), Vec<u8>> {
+        self.pausable.when_not_paused()?;
+
+        if amount == 0 {
+            return Err(MarketError::ZeroBuyAmount(ZeroBuyAmount {}).into());
+        }
+
+        match self.campaign_state.get().to::<u8>() {
+            CAMPAIGN_OPEN => return self.contribute(buyer, name, amount),
+            CAMPAIGN_FAILED => return Err(invalid_campaign_state(CAMPAIGN_FAILED)),
+            _ => {}
+        }
+
+        let payment_token = IERC20::new(self.price_feeds.get(name).token.get());
+
+        let amount_needed = self.get_amount_price(amount, name)?;
+
+        let cooling_off_period = self.cooling_off_period.get();
+        if !cooling_off_period.is_zero() {
+            // The payment is held here until the cooling-off period ends, so a
+            // refund can always be paid. See [`Market::release_purchases`]
+            self.pull_payment(payment_token.address, buyer, amount_needed)?;
+        } else if self.escrow_mode.get() {
+            // In escrow mode the whole amount is kept here, and each payee
+            // withdraws its part later. A payee that can't receive tokens
+            // doesn't stop the sales that way
+            self.pull_payment(payment_token.address, buyer, amount_needed)?;
+            self.escrow_for_payees(payment_token.address, amount_needed, name);
+        } else {
+            for (payee, amount) in self.split_between_payees(amount_needed) {
+                let success =
+                    payment_token.transfer_from(Call::new_in(self), buyer, payee, amount)?;
+                if !success {
+                    return Err(MarketError::PaymentFailed(PaymentFailed {}).into());
+                }
+
+                evm::log(Collected {
+                    token: payment_token.address,
+                    amount,
+                    aggregator: name,
+                    vault: payee,
+                });
+            }
+        }
+
+        let seabrick = ISeabrick::new(self.nft_token.get());
+        let refundable_until = U256::from(block::timestamp()) + cooling_off_period;
+
+        if amount == 1 {
+            // Mint the token to the buyer address
+            let id = seabrick.mint(Call::new_in(self), buyer)?;
+
+            if !cooling_off_period.is_zero() {
+                self.record_purchase(
+                    id,
+                    buyer,
+                    payment_token.address,
+                    amount_needed,
+                    name,
+                    refundable_until,
+                );
+            }
+
+            evm::log(Buy {
+                buyer,
+                id,
+                amountSpent: amount_needed,
+                aggregator: name,
+            });
+        } else {
+            // The ids start at zero, so the first one minted is the current supply
+            let id_init = seabrick.total_supply(Call::new_in(self))?;
+            let individual_price = amount_needed.div_ceil(U256::from(amount));
+
+            seabrick.mint_batch(Call::new_in(self), buyer, amount.into())?;
+
+            // Each token refunds its own part of the payment. The last one gets the
+            // rounding remainder, so all of them together give back exactly what was paid
+            let mut remaining = amount_needed;
+
+            for i in 0..amount {
+                let id = id_init + U256::from(i);
+
+                if !cooling_off_period.is_zero() {
+                    let part = if i == amount - 1 {
+                        remaining
+                    } else {
+                        amount_needed / U256::from(amount)
+                    };
+                    remaining -= part;
+
+                    self.record_purchase(
+                        id,
+                        buyer,
+                        payment_token.address,
+                        part,
+                        name,
+                        refundable_until,
+                    );
+                }
+
+                evm::log(Buy {
+                    buyer,
+                    id,
+                    amountSpent: individual_price,
+                    aggregator: name,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the payment of `id` so it can be refunded until `refundable_until`
+    fn record_purchase(
+        &mut self,
+        id: U256,
+        buyer: Address,
+        token: Address,
+        amount: U256,
+        name: FixedBytes<32>,
+        refundable_until: U256,
+    ) {
+        let mut purchase = self.purchases.setter(id);
+        purchase.buyer.set(buyer);
+        purchase.token.set(token);
+        purchase.amount.set(amount);
+        purchase.aggregator.set(name);
+        purchase.refundable_until.set(refundable_until);
+    }
+
+    /// Forgets the payment of `id`, once it's refunded or released
+    fn clear_purchase(&mut self, id: U256) {
+        let mut purchase = self.purchases.setter(id);
+        purchase.buyer.set(Address::ZERO);
+        purchase.token.set(Address::ZERO);
+        purchase.amount.set(U256::ZERO);
+        purchase.aggregator.set(FixedBytes::ZERO);
+        purchase.refundable_until.set(U256::ZERO);
+    }
+
+    /// Splits `amount` between the payees by their shares. The last one gets the
+    /// rounding remainder, so the whole amount is always collected.
+    /// Payees whose part is zero are left out.
+    fn split_between_payees(&self, amount: U256) -> Vec<(Address, U256)> {
+        let (payees, shares) = self.payees_list();
+        let count = payees.len();
+        let mut parts = Vec::with_capacity(count);
+        let mut remaining = amount;
+
+        for (i, (payee, share)) in payees.into_iter().zip(shares).enumerate() {
+            let part = if i == count - 1 {
+                remaining
+            } else {
+                amount * U256::from(share) / U256::from(TOTAL_SHARES)
+            };
+            remaining -= part;
+
+            if !part.is_zero() {
+                parts.push((payee, part));
+            }
+        }
+
+        parts
+    }
+
+    /// Moves `amount` of `token` from `from` to the market
+    fn pull_payment(&mut self, token: Address, from: Address, amount: U256) -> Result<(), Vec<u8>> {
+        let success = IERC20::new(token).transfer_from(
+            Call::new_in(self),
+            from,
+            contract::address(),
+            amount,
+        )?;
+        if !success {
+            return Err(MarketError::PaymentFailed(PaymentFailed {}).into());
+        }
+
+        Ok(())
+    }
+
+    /// Credits `amount` of `token`, already held by the market, to the payees
+    /// pending balances. They take it out with [`Market::withdraw`].
+    fn escrow_for_payees(&mut self, token: Address, amount: U256, name: FixedBytes<32>) {
+        let mut escrowed = self.escrowed.setter(token);
+        let total = escrowed.get() + amount;
+        escrowed.set(total);
+
+        for (payee, part) in self.split_between_payees(amount) {
+            let mut pending = self.pending_balances.setter(token);
+            let mut balance = pending.setter(payee);
+            let total = balance.get() + part;
+            balance.set(total);
+
+            evm::log(Escrowed {
+                token,
+                amount: part,
+                aggregator: name,
+                payee,
+            });
+        }
+    }
+
+    /// Takes the payment of `amount` NFTs while the campaign is open. The tokens
+    /// are kept in the market for `buyer`, and the NFTs can be claimed once the
+    /// campaign succeeds. Only the tokens the market actually received count, in
+    /// the refunds and in the USD raised, so a token that takes a fee on transfers
+    /// can't get the soft cap met with funds the market doesn't have.
+    fn contribute(
+        &mut self,
+        buyer: Address,
+        name: FixedBytes<32>,
+        amount: u8,
+    ) -> Result<(), Vec<u8>> {
+        if U256::from(block::timestamp()) >= self.campaign_deadline.get() {
+            return Err(MarketError::CampaignEnded(CampaignEnded {}).into());
+        }
+
+        let token = self.price_feeds.get(name).token.get();
+        let (rate, scale) = self.usd_rate(name)?;
+        let amount_needed = (self.price.get() * scale).div_ceil(rate) * U256::from(amount);
+
+        let payment_token = IERC20::new(token);
+        let balance_before = payment_token.balance_of(Call::new_in(self), contract::address())?;
+        self.pull_payment(token, buyer, amount_needed)?;
+        let balance_after = payment_token.balance_of(Call::new_in(self), contract::address())?;
+        let received = balance_after.saturating_sub(balance_before);
+
+        // Remember every token used, so they can be released or refunded later
+        if self.campaign_totals.get(token).is_zero() {
+            self.campaign_tokens.push(token);
+        }
+        let mut total = self.campaign_totals.setter(token);
+        let new_total = total.get() + received;
+        total.set(new_total);
+
+        let mut contributions = self.campaign_contributions.setter(buyer);
+        let mut contribution = contributions.setter(token);
+        let new_contribution = contribution.get() + received;
+        contribution.set(new_contribution);
+
+        let nfts = U256::from(amount);
+        let mut owed = self.campaign_nfts.setter(buyer);
+        let new_owed = owed.get() + nfts;
+        owed.set(new_owed);
+
+        // Rounded down, so the soft cap is only met with the full amount
+        let raised = self.campaign_raised.get() + received * rate / scale;
+        self.campaign_raised.set(raised);
+
+        evm::log(CampaignContribution {
+            buyer,
+            token,
+            amount: received,
+            nfts,
+            aggregator: name,
+        });
+
+        Ok(())
+    }
+}
+
+/// Error for a campaign operation not allowed in the current `state`
+fn invalid_campaign_state(state: u8) -> Vec<u8> {
+    MarketError::InvalidCampaignState(InvalidCampaignState { state }).into()
 }
 
 #[public]
-#[inherit(Ownable)]
+#[inherit(Initialization, Ownable, Timelock, Pausable, Erc165)]
 impl Market {
+    #[allow(clippy::too_many_arguments)]
     pub fn initialization(
         &mut self,
         ownership_contract: Address,
+        min_delay: U256,
         price: U256,
         nft_token: Address,
-        claim_vault: Address,
+        payees: Vec<Address>,
+        shares: Vec<u16>,
         names: Vec<FixedBytes<32>>,
         agregators: Vec<Address>,
         tokens: Vec<Address>,
@@ -184,7 +789,14 @@ impl Market {
         self.init._check_init()?;
 
         // Set contract ownership contract address
-        self.ownable.set_ownership_contract(ownership_contract);
+        self.ownable.set_ownership_contract(ownership_contract)?;
+
+        // Set the minimum delay for the timelocked owner calls
+        self.timelock._set_min_delay(min_delay);
+
+        // Report the market interface through ERC-165
+        self.erc165
+            ._register_interface(ISEABRICK_MARKET_INTERFACE_ID);
 
         // Set NFT price
         self.price.set(price);
@@ -192,11 +804,8 @@ impl Market {
         // Set NFT token contract
         self.nft_token.set(nft_token);
 
-        // Set the claim vault address (when claiming, the tokens will be transfer to this address)
-        if claim_vault == Address::ZERO {
-            return Err(MarketError::VaultZeroAddress(VaultZeroAddress {}).into());
-        }
-        self.claim_vault.set(claim_vault);
+        // Set the payees (when claiming, the tokens will be split between them)
+        self.set_payees_internal(payees, shares)?;
 
         // Add the agregators
         self.set_aggregators_internal(names, agregators, tokens)?;
@@ -204,12 +813,11 @@ impl Market {
         evm::log(SaleDetails {
             price,
             nftAddress: nft_token,
-            claimVault: claim_vault,
             ownershipContract: ownership_contract,
         });
 
         // Change contract state to already initialized
-        self.init._set_init(true);
+        self.init._set_initialized(1);
 
         Ok(())
     }
@@ -220,15 +828,65 @@ impl Market {
         agregators: Vec<Address>,
         tokens: Vec<Address>,
     ) -> Result<(), Vec<u8>> {
-        self.ownable.only_owner()?;
+        self.ownable.only_role(PRICE_ADMIN)?;
+        self.timelock._consume(
+            &setAgregatorsCall {
+                names: names.clone(),
+                agregators: agregators.clone(),
+                tokens: tokens.clone(),
+            }
+            .abi_encode(),
+        )?;
 
         self.set_aggregators_internal(names, agregators, tokens)?;
 
         Ok(())
     }
 
+    /// Sets the `name` feed to a fixed `rate` for `token`, replacing its oracle if
+    /// it had one. `rate` is the USD value of one token, with 8 decimals (eg.
+    /// `100_000_000` for a stablecoin worth 1 USD). This call is timelocked.
+    pub fn set_fixed_rate(
+        &mut self,
+        name: FixedBytes<32>,
+        token: Address,
+        rate: U256,
+    ) -> Result<(), Vec<u8>> {
+        self.ownable.only_role(PRICE_ADMIN)?;
+        self.timelock
+            ._consume(&setFixedRateCall { name, token, rate }.abi_encode())?;
+
+        if rate.is_zero() {
+            return Err(MarketError::ZeroRate(ZeroRate {}).into());
+        }
+
+        let mut feed = self.price_feeds.setter(name);
+        feed.agregator_address.set(Address::ZERO);
+        feed.token.set(token);
+        feed.fixed_rate.set(true);
+        feed.rate.set(rate);
+
+        evm::log(FixedRateAdded { name, token, rate });
+
+        Ok(())
+    }
+
+    /// Returns the aggregator, payment token, whether it has a fixed rate and
+    /// the rate of the `name` feed
+    pub fn price_feed(&self, name: FixedBytes<32>) -> (Address, Address, bool, U256) {
+        let feed = self.price_feeds.get(name);
+        (
+            feed.agregator_address.get(),
+            feed.token.get(),
+            feed.fixed_rate.get(),
+            feed.rate.get(),
+        )
+    }
+
     pub fn set_price(&mut self, price: U256) -> Result<(), Vec<u8>> {
-        self.ownable.only_owner()?;
+        self.ownable.only_role(PRICE_ADMIN)?;
+        self.timelock
+            ._consume(&setPriceCall { price }.abi_encode())?;
 
         if price == U256::ZERO {
             return Err(MarketError::ZeroPrice(ZeroPrice {}).into());
@@ -242,78 +900,1247 @@ impl Market {
         Ok(())
     }
 
-    pub fn set_vault(&mut self, vault: Address) -> Result<(), Vec<u8>> {
+    /// Replaces the payees the sales are split between. `shares` are in basis
+    /// points and must add up to 10000. This call is timelocked.
+    pub fn set_payees(&mut self, payees: Vec<Address>, shares: Vec<u16>) -> Result<(), Vec<u8>> {
+        self.ownable.only_role(VAULT_ADMIN)?;
+        self.timelock._consume(
+            &setPayeesCall {
+                payees: payees.clone(),
+                shares: shares.clone(),
+            }
+            .abi_encode(),
+        )?;
+
+        self.set_payees_internal(payees, shares)
+    }
+
+    /// Turns the escrow mode on or off. While it's on, the sale proceeds are kept
+    /// in the market and each payee withdraws its part with [`Market::withdraw`].
+    /// It's not timelocked, since it doesn't change who gets the funds.
+    pub fn set_escrow_mode(&mut self, enabled: bool) -> Result<(), Vec<u8>> {
+        self.ownable.only_role(VAULT_ADMIN)?;
+
+        self.escrow_mode.set(enabled);
+
+        evm::log(EscrowModeChanged { enabled });
+
+        Ok(())
+    }
+
+    /// Whether the sale proceeds are kept in the market for the payees to withdraw
+    pub fn escrow_mode(&self) -> bool {
+        self.escrow_mode.get()
+    }
+
+    /// Amount of `token` escrowed for `payee`, pending to withdraw
+    pub fn pending_balance(&self, token: Address, payee: Address) -> U256 {
+        self.pending_balances.get(token).get(payee)
+    }
+
+    /// Total amount of `token` escrowed for all the payees
+    pub fn escrowed_balance(&self, token: Address) -> U256 {
+        self.escrowed.get(token)
+    }
+
+    /// Sends to the caller all the `token` escrowed for it. Former payees can
+    /// still withdraw what was escrowed for them before being replaced.
+    pub fn withdraw(&mut self, token: Address) -> Result<U256, Vec<u8>> {
+        self.reentrancy_guard._enter()?;
+
+        let payee = msg::sender();
+        let amount = self.pending_balances.get(token).get(payee);
+        if amount.is_zero() {
+            return Err(MarketError::NothingToWithdraw(NothingToWithdraw { token, payee }).into());
+        }
+
+        // Clear the balance before the transfer
+        self.pending_balances
+            .setter(token)
+            .setter(payee)
+            .set(U256::ZERO);
+        let mut escrowed = self.escrowed.setter(token);
+        let total = escrowed.get() - amount;
+        escrowed.set(total);
+
+        let success = IERC20::new(token).transfer(Call::new_in(self), payee, amount)?;
+        if !success {
+            return Err(MarketError::PaymentFailed(PaymentFailed {}).into());
+        }
+
+        evm::log(Withdrawn {
+            token,
+            payee,
+            amount,
+        });
+
+        self.reentrancy_guard._exit();
+
+        Ok(amount)
+    }
+
+    /// Starts an all-or-nothing campaign. Until `deadline` the buys are paid to
+    /// the market instead of the payees, and no NFT is minted. If `soft_cap` USD
+    /// (in the same units as the price) is raised, the buyers claim their NFTs
+    /// and the funds go to the payees. Otherwise, each buyer gets back what it paid.
+    /// There can only be one campaign per market. Timelocked, as it changes where
+    /// the payments go.
+    pub fn start_campaign(&mut self, soft_cap: U256, deadline: U256) -> Result<(), Vec<u8>> {
         self.ownable.only_owner()?;
+        self.timelock._consume(
+            &startCampaignCall {
+                softCap: soft_cap,
+                deadline,
+            }
+            .abi_encode(),
+        )?;
 
-        if vault == Address::ZERO {
-            return Err(MarketError::VaultZeroAddress(VaultZeroAddress {}).into());
+        let state = self.campaign_state.get().to::<u8>();
+        if state != CAMPAIGN_NONE {
+            return Err(invalid_campaign_state(state));
+        }
+
+        if soft_cap.is_zero() || deadline <= U256::from(block::timestamp()) {
+            return Err(MarketError::InvalidCampaign(InvalidCampaign {}).into());
         }
 
-        // Set new claim vault address
-        self.claim_vault.set(vault);
+        self.campaign_state.set(U8::from(CAMPAIGN_OPEN));
+        self.campaign_soft_cap.set(soft_cap);
+        self.campaign_deadline.set(deadline);
 
-        evm::log(ClaimVaultAdded {
-            newClaimVault: vault,
+        evm::log(CampaignStarted {
+            softCap: soft_cap,
+            deadline,
         });
 
         Ok(())
     }
 
-    pub fn buy(&mut self, buyer: Address, name: FixedBytes<32>, amount: u8) -> Result<(), Vec<u8>> {
-        if amount == 0 {
-            return Err(MarketError::ZeroBuyAmount(ZeroBuyAmount {}).into());
+    /// Ends the campaign. It can be called by anyone once the deadline has passed,
+    /// or earlier if the soft cap is met. On success, the raised funds are escrowed
+    /// for the payees to withdraw.
+    pub fn finalize_campaign(&mut self) -> Result<bool, Vec<u8>> {
+        let state = self.campaign_state.get().to::<u8>();
+        if state != CAMPAIGN_OPEN {
+            return Err(invalid_campaign_state(state));
         }
 
-        let payment_token = IERC20::new(self.price_feeds.get(name).token.get());
+        let raised = self.campaign_raised.get();
+        let succeeded = raised >= self.campaign_soft_cap.get();
+        if !succeeded && U256::from(block::timestamp()) < self.campaign_deadline.get() {
+            return Err(MarketError::CampaignNotEnded(CampaignNotEnded {}).into());
+        }
 
-        let amount_needed = self.get_amount_price(amount, name)?;
+        if succeeded {
+            self.campaign_state.set(U8::from(CAMPAIGN_SUCCEEDED));
+
+            // Escrowed instead of sent, so a payee that can't receive tokens
+            // doesn't keep the buyers from claiming
+            for i in 0..self.campaign_tokens.len() {
+                if let Some(token) = self.campaign_tokens.get(i) {
+                    let total = self.campaign_totals.get(token);
+                    self.campaign_totals.setter(token).set(U256::ZERO);
+                    self.escrow_for_payees(token, total, FixedBytes::ZERO);
+                }
+            }
+        } else {
+            self.campaign_state.set(U8::from(CAMPAIGN_FAILED));
+        }
 
-        // Transfer the tokens (ERC20) to the claim vault defined
-        let claim_vault = self.claim_vault.get();
+        evm::log(CampaignFinalized { succeeded, raised });
 
-        let success =
-            payment_token.transfer_from(Call::new_in(self), buyer, claim_vault, amount_needed)?;
-        if !success {
-            return Err(MarketError::PaymentFailed(PaymentFailed {}).into());
+        Ok(succeeded)
+    }
+
+    /// Mints to the caller the NFTs it paid for during a successful campaign, up to
+    /// [`MAX_CLAIM_BATCH`] per call. The rest stay in [`Self::campaign_nfts`] to be
+    /// claimed with another call.
+    pub fn claim(&mut self) -> Result<(), Vec<u8>> {
+        self.reentrancy_guard._enter()?;
+
+        let state = self.campaign_state.get().to::<u8>();
+        if state != CAMPAIGN_SUCCEEDED {
+            return Err(invalid_campaign_state(state));
         }
 
-        // Emit the event Claim event (we can change the event name)
-        // total_collected
-        evm::log(Collected {
-            token: payment_token.address,
-            amount: amount_needed,
-            aggregator: name,
-            vault: claim_vault,
-        });
+        let buyer = msg::sender();
+        let owed = self.campaign_nfts.get(buyer);
+        if owed.is_zero() {
+            return Err(MarketError::NothingToClaim(NothingToClaim { buyer }).into());
+        }
+
+        let amount = owed.min(U256::from(MAX_CLAIM_BATCH));
+        self.campaign_nfts.setter(buyer).set(owed - amount);
 
         let seabrick = ISeabrick::new(self.nft_token.get());
+        let first_id = seabrick.total_supply(Call::new_in(self))?;
+        seabrick.mint_batch(Call::new_in(self), buyer, amount.to::<u32>())?;
 
-        if amount == 1 {
-            // Mint the token to the buyer address
-            let id = seabrick.mint(Call::new_in(self), buyer)?;
+        evm::log(CampaignClaimed {
+            buyer,
+            firstId: first_id,
+            amount,
+        });
 
-            evm::log(Buy {
+        self.reentrancy_guard._exit();
+
+        Ok(())
+    }
+
+    /// Gives back to the caller every token it paid during a failed campaign
+    pub fn refund(&mut self) -> Result<(), Vec<u8>> {
+        self.reentrancy_guard._enter()?;
+
+        let state = self.campaign_state.get().to::<u8>();
+        if state != CAMPAIGN_FAILED {
+            return Err(invalid_campaign_state(state));
+        }
+
+        let buyer = msg::sender();
+        let mut refunded = false;
+
+        for i in 0..self.campaign_tokens.len() {
+            let Some(token) = self.campaign_tokens.get(i) else {
+                continue;
+            };
+
+            let amount = self.campaign_contributions.get(buyer).get(token);
+            if amount.is_zero() {
+                continue;
+            }
+
+            // Clear the contribution before the transfer
+            self.campaign_contributions
+                .setter(buyer)
+                .setter(token)
+                .set(U256::ZERO);
+            let mut total = self.campaign_totals.setter(token);
+            let new_total = total.get() - amount;
+            total.set(new_total);
+
+            let success = IERC20::new(token).transfer(Call::new_in(self), buyer, amount)?;
+            if !success {
+                return Err(MarketError::PaymentFailed(PaymentFailed {}).into());
+            }
+
+            evm::log(CampaignRefunded {
                 buyer,
-                id,
-                amountSpent: amount_needed,
-                aggregator: name,
+                token,
+                amount,
             });
-        } else {
-            let id_init = (seabrick.total_supply(Call::new_in(self))?) + U256::from(1u8);
-            let individual_price = amount_needed.div_ceil(U256::from(amount));
+            refunded = true;
+        }
 
-            seabrick.mint_batch(Call::new_in(self), buyer, amount)?;
+        if !refunded {
+            return Err(MarketError::NothingToRefund(NothingToRefund { buyer }).into());
+        }
 
-            for i in 0..amount.into() {
-                evm::log(Buy {
-                    buyer,
-                    id: id_init + U256::from(i),
-                    amountSpent: individual_price,
-                    aggregator: name,
-                });
+        self.campaign_nfts.setter(buyer).set(U256::ZERO);
+
+        self.reentrancy_guard._exit();
+
+        Ok(())
+    }
+
+    /// Returns the campaign state (one of the `CAMPAIGN_*` values), soft cap,
+    /// deadline and USD raised so far
+    pub fn campaign(&self) -> (u8, U256, U256, U256) {
+        (
+            self.campaign_state.get().to(),
+            self.campaign_soft_cap.get(),
+            self.campaign_deadline.get(),
+            self.campaign_raised.get(),
+        )
+    }
+
+    /// Amount of `token` received from `buyer` during the campaign, still held by the market
+    pub fn campaign_contribution(&self, buyer: Address, token: Address) -> U256 {
+        self.campaign_contributions.get(buyer).get(token)
+    }
+
+    /// NFTs `buyer` paid for during the campaign and has not claimed yet
+    pub fn campaign_nfts(&self, buyer: Address) -> U256 {
+        self.campaign_nfts.get(buyer)
+    }
+
+    /// Sets the seconds after a buy during which the buyer can return the token for
+    /// a full refund in the token it paid with. Zero turns it off. It only applies to
+    /// the buys made after the change, and not to the NFTs claimed from a campaign.
+    /// The market must be a burner of the NFT contract. This call is timelocked.
+    pub fn set_cooling_off_period(&mut self, period: U256) -> Result<(), Vec<u8>> {
+        self.ownable.only_owner()?;
+        self.timelock
+            ._consume(&setCoolingOffPeriodCall { period }.abi_encode())?;
+
+        self.cooling_off_period.set(period);
+
+        evm::log(CoolingOffPeriodChanged { period });
+
+        Ok(())
+    }
+
+    /// Seconds after a buy during which the token can be returned. Zero means there is none
+    pub fn cooling_off_period(&self) -> U256 {
+        self.cooling_off_period.get()
+    }
+
+    /// Returns the buyer, payment token, amount paid and the timestamp until which
+    /// `token_id` can be returned. All zero if its payment is not held anymore.
+    pub fn purchase(&self, token_id: U256) -> (Address, Address, U256, U256) {
+        let purchase = self.purchases.get(token_id);
+        (
+            purchase.buyer.get(),
+            purchase.token.get(),
+            purchase.amount.get(),
+            purchase.refundable_until.get(),
+        )
+    }
+
+    /// Returns `token_id` within the cooling-off period. The token is burned and
+    /// the caller, who must be its buyer and still own it, gets back what it paid.
+    pub fn refund_purchase(&mut self, token_id: U256) -> Result<U256, Vec<u8>> {
+        self.reentrancy_guard._enter()?;
+
+        let purchase = self.purchases.get(token_id);
+        let buyer = purchase.buyer.get();
+        let token = purchase.token.get();
+        let amount = purchase.amount.get();
+        let refundable_until = purchase.refundable_until.get();
+
+        if buyer.is_zero() {
+            return Err(MarketError::UnknownPurchase(UnknownPurchase { tokenId: token_id }).into());
+        }
+
+        if msg::sender() != buyer || U256::from(block::timestamp()) >= refundable_until {
+            return Err(MarketError::NotRefundable(NotRefundable { tokenId: token_id }).into());
+        }
+
+        // Clear the purchase before the external calls
+        self.clear_purchase(token_id);
+
+        ISeabrick::new(self.nft_token.get()).burn_from(Call::new_in(self), buyer, token_id)?;
+
+        let success = IERC20::new(token).transfer(Call::new_in(self), buyer, amount)?;
+        if !success {
+            return Err(MarketError::PaymentFailed(PaymentFailed {}).into());
+        }
+
+        evm::log(Refunded {
+            buyer,
+            tokenId: token_id,
+            token,
+            amount,
+        });
+
+        self.reentrancy_guard._exit();
+
+        Ok(amount)
+    }
+
+    /// Escrows for the payees the payments of `token_ids` whose cooling-off period
+    /// is over. Anyone can call it.
+    pub fn release_purchases(&mut self, token_ids: Vec<U256>) -> Result<(), Vec<u8>> {
+        let now = U256::from(block::timestamp());
+
+        for token_id in token_ids {
+            let purchase = self.purchases.get(token_id);
+            let token = purchase.token.get();
+            let amount = purchase.amount.get();
+            let name = purchase.aggregator.get();
+
+            if purchase.buyer.get().is_zero() {
+                return Err(
+                    MarketError::UnknownPurchase(UnknownPurchase { tokenId: token_id }).into(),
+                );
+            }
+
+            if now < purchase.refundable_until.get() {
+                return Err(
+                    MarketError::StillRefundable(StillRefundable { tokenId: token_id }).into(),
+                );
             }
+
+            self.clear_purchase(token_id);
+            self.escrow_for_payees(token, amount, name);
         }
 
         Ok(())
     }
+
+    /// Returns the payees and their shares, in basis points
+    pub fn payees(&self) -> Result<(Vec<Address>, Vec<u16>), Vec<u8>> {
+        Ok(self.payees_list())
+    }
+
+    /// Schedules an owner call (`data` is the full calldata) to be executable from `eta`.
+    /// `eta` must be at least `min_delay` seconds from now.
+    /// It requires the same role needed to execute the call.
+    pub fn schedule(&mut self, data: Bytes, eta: U256) -> Result<FixedBytes<32>, Vec<u8>> {
+        self.only_scheduler(&data)?;
+
+        Ok(self.timelock._schedule(&data, eta)?)
+    }
+
+    /// Cancels the scheduled call `data`
+    pub fn cancel(&mut self, data: Bytes) -> Result<(), Vec<u8>> {
+        self.only_scheduler(&data)?;
+        self.timelock._cancel(keccak(&data))?;
+
+        Ok(())
+    }
+
+    /// Changes the minimum delay for scheduled calls. This call is timelocked as well.
+    pub fn set_min_delay(&mut self, delay: U256) -> Result<(), Vec<u8>> {
+        self.ownable.only_owner()?;
+        self.timelock
+            ._consume(&setMinDelayCall { delay }.abi_encode())?;
+
+        self.timelock._set_min_delay(delay);
+
+        Ok(())
+    }
+
+    /// Stops the sales. Can only be called by a pauser.
+    pub fn pause(&mut self) -> Result<(), Vec<u8>> {
+        self.ownable.only_role(PAUSER)?;
+        self.pausable._pause()?;
+
+        Ok(())
+    }
+
+    /// Resumes the sales. Can only be called by a pauser.
+    pub fn unpause(&mut self) -> Result<(), Vec<u8>> {
+        self.ownable.only_role(PAUSER)?;
+        self.pausable._unpause()?;
+
+        Ok(())
+    }
+
+    /// Sells `amount` NFTs to `buyer`, paid with the token of the `name` price feed.
+    /// It can't be reentered from the payment token or the NFT contract.
+    pub fn buy(&mut self, buyer: Address, name: FixedBytes<32>, amount: u8) -> Result<(), Vec<u8>> {
+        self.reentrancy_guard._enter()?;
+        self.buy_internal(buyer, name, amount)?;
+        self.reentrancy_guard._exit();
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+    use seabrick_common::{
+        erc165::{supportsInterfaceCall, ERC165_INTERFACE_ID},
+        ownable::ERC173_INTERFACE_ID,
+        reentrancy_guard::ReentrantCall,
+        testing::{self, SENDER},
+    };
+    use stylus_sdk::{
+        alloy_primitives::{address, fixed_bytes},
+        alloy_sol_types::{SolError, SolEvent, SolValue},
+        storage::StorageType,
+    };
+
+    const NFT: Address = address!("00000000000000000000000000000000000000e1");
+    const TOKEN: Address = address!("00000000000000000000000000000000000000e2");
+    const FEED: Address = address!("00000000000000000000000000000000000000e3");
+    const VAULT: Address = address!("00000000000000000000000000000000000000e4");
+    const OWNERSHIP: Address = address!("00000000000000000000000000000000000000e0");
+    const ALICE: Address = address!("000000000000000000000000000000000000a11c");
+    const BOB: Address = address!("0000000000000000000000000000000000000b0b");
+
+    /// Name of the token price feed
+    const USDC_USD: FixedBytes<32> = FixedBytes::with_last_byte(1);
+
+    sol! {
+        function decimals() external view returns (uint8);
+        function latestRoundData() external view returns (uint80, int256, uint256, uint256, uint80);
+        function transferFrom(address from, address to, uint256 value) external returns (bool);
+        function transfer(address to, uint256 value) external returns (bool);
+        function balanceOf(address account) external view returns (uint256);
+        function mint(address to) external returns (uint256);
+        function mintBatch(address to, uint32 amount) external;
+        function totalSupply() external returns (uint256);
+        function burnFrom(address from, uint256 token_id) external;
+    }
+
+    /// Answers the calls made by a purchase: the feed prices the token at 3 USD with
+    /// 8 decimals, the token has 6 decimals, the payments succeed and the NFT mints id 1.
+    /// The ownership contract reports ERC-173, and the test sender as the owner.
+    fn contracts() {
+        testing::on_call(answer);
+    }
+
+    /// Answer of the other contracts, see [`contracts`]
+    fn answer(to: Address, calldata: &[u8]) -> Result<Vec<u8>, Vec<u8>> {
+        let selector: [u8; 4] = calldata[..4].try_into().unwrap();
+        match (to, selector) {
+            (OWNERSHIP, supportsInterfaceCall::SELECTOR) => {
+                let call = supportsInterfaceCall::abi_decode(calldata, true).unwrap();
+                let ids = [ERC165_INTERFACE_ID, ERC173_INTERFACE_ID];
+                Ok(ids.contains(&call.interface_id).abi_encode())
+            }
+            (OWNERSHIP, _) => Ok(SENDER.abi_encode()),
+            (FEED, decimalsCall::SELECTOR) => Ok(decimalsCall::abi_encode_returns(&(8,))),
+            (FEED, latestRoundDataCall::SELECTOR) => {
+                let answer = U256::from(300_000_000);
+                Ok((U256::ZERO, answer, U256::ZERO, U256::ZERO, U256::ZERO).abi_encode())
+            }
+            (TOKEN, decimalsCall::SELECTOR) => Ok(decimalsCall::abi_encode_returns(&(6,))),
+            (TOKEN, transferFromCall::SELECTOR | transferCall::SELECTOR) => Ok(true.abi_encode()),
+            (NFT, mintCall::SELECTOR) => Ok(U256::from(1).abi_encode()),
+            (NFT, mintBatchCall::SELECTOR) => Ok(vec![]),
+            (NFT, totalSupplyCall::SELECTOR) => Ok(U256::from(7).abi_encode()),
+            _ => Err(vec![]),
+        }
+    }
+
+    /// A market selling the NFT at 10 USD, paid in the token of [`USDC_USD`] to
+    /// [`VAULT`]. The test sender owns it.
+    fn market() -> Market {
+        testing::reset();
+        contracts();
+
+        let mut market = unsafe { Market::new(U256::ZERO, 0) };
+        market.ownable._ownership.set(OWNERSHIP);
+        market.price.set(U256::from(10));
+        market.nft_token.set(NFT);
+        market
+            .set_payees_internal(vec![VAULT], vec![10_000])
+            .unwrap();
+        market
+            .set_aggregators_internal(vec![USDC_USD], vec![FEED], vec![TOKEN])
+            .unwrap();
+        market
+    }
+
+    /// Payee and amount of each `Collected` event emitted
+    fn collected() -> Vec<(Address, U256)> {
+        testing::take_logs()
+            .iter()
+            .filter(|log| log.topics[0] == Collected::SIGNATURE_HASH.0)
+            .map(|log| Collected::abi_decode_data(&log.data, true).unwrap())
+            .map(|(_, amount, _, payee)| (payee, amount))
+            .collect()
+    }
+
+    #[test]
+    fn buyer_pays_the_vault_and_gets_the_nft() {
+        let mut market = market();
+        testing::take_logs();
+
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+
+        let targets: Vec<Address> = testing::take_calls().iter().map(|call| call.0).collect();
+        assert!(targets.contains(&TOKEN) && targets.contains(&NFT));
+
+        let buy = testing::take_logs()
+            .iter()
+            .find(|log| log.topics[0] == Buy::SIGNATURE_HASH.0)
+            .map(|log| Buy::abi_decode_data(&log.data, true).unwrap())
+            .unwrap();
+        assert_eq!(buy.0, SENDER);
+        assert_eq!(buy.1, U256::from(1));
+        // 10 USD at 3 USD per token, with 6 decimals and rounded up
+        assert_eq!(buy.2, U256::from(3_333_334));
+    }
+
+    #[test]
+    fn buy_cannot_be_reentered() {
+        let mut market = market();
+
+        // As if a purchase was running and called back into the market
+        market.reentrancy_guard._enter().unwrap();
+        assert_eq!(
+            market.buy(SENDER, USDC_USD, 1),
+            Err(ReentrantCall {}.abi_encode())
+        );
+
+        // Once it finishes, the guard is released for the next purchases
+        market.reentrancy_guard._exit();
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+    }
+
+    #[test]
+    fn initialization_reports_the_market_interface() {
+        testing::reset();
+        contracts();
+        let mut market = unsafe { Market::new(U256::ZERO, 0) };
+        assert_eq!(ISEABRICK_MARKET_INTERFACE_ID, fixed_bytes!("3725fe31"));
+        assert!(!market
+            .erc165
+            .supports_interface(ISEABRICK_MARKET_INTERFACE_ID));
+
+        let initialized = market.initialization(
+            OWNERSHIP,
+            U256::ZERO,
+            U256::from(10),
+            NFT,
+            vec![VAULT],
+            vec![10_000],
+            vec![USDC_USD],
+            vec![FEED],
+            vec![TOKEN],
+        );
+
+        assert!(initialized.is_ok());
+        assert!(market.erc165.supports_interface(ERC165_INTERFACE_ID));
+        assert!(market
+            .erc165
+            .supports_interface(ISEABRICK_MARKET_INTERFACE_ID));
+    }
+
+    #[test]
+    fn proceeds_are_split_between_the_payees() {
+        let mut market = market();
+        let shares = vec![3_333, 3_333, 3_334];
+        assert!(market
+            .set_payees_internal(vec![ALICE, BOB, VAULT], shares.clone())
+            .is_ok());
+        assert_eq!(market.payees(), Ok((vec![ALICE, BOB, VAULT], shares)));
+        testing::take_logs();
+
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+
+        // The last payee gets the rounding remainder, so the whole price is paid
+        assert_eq!(
+            collected(),
+            [
+                (ALICE, U256::from(1_111_000)),
+                (BOB, U256::from(1_111_000)),
+                (VAULT, U256::from(1_111_334)),
+            ]
+        );
+    }
+
+    #[test]
+    fn payees_without_a_share_are_skipped() {
+        let mut market = market();
+        assert!(market
+            .set_payees_internal(vec![ALICE, VAULT], vec![10_000, 0])
+            .is_ok());
+        testing::take_logs();
+
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+
+        assert_eq!(collected(), [(ALICE, U256::from(3_333_334))]);
+    }
+
+    #[test]
+    fn payee_shares_must_add_up_to_the_total() {
+        let mut market = market();
+
+        assert_eq!(
+            market.set_payees_internal(vec![ALICE, BOB], vec![5_000, 4_999]),
+            Err(InvalidShares {
+                total: U256::from(9_999)
+            }
+            .abi_encode())
+        );
+        assert_eq!(
+            market.set_payees_internal(vec![ALICE, BOB], vec![10_000]),
+            Err(MismatchPayees {}.abi_encode())
+        );
+        assert_eq!(
+            market.set_payees_internal(vec![ALICE, Address::ZERO], vec![5_000, 5_000]),
+            Err(VaultZeroAddress {}.abi_encode())
+        );
+        assert_eq!(market.payees(), Ok((vec![VAULT], vec![10_000])));
+    }
+
+    #[test]
+    fn payee_changes_wait_for_the_timelock() {
+        let mut market = market();
+        let data = setPayeesCall {
+            payees: vec![ALICE],
+            shares: vec![10_000],
+        }
+        .abi_encode();
+
+        assert!(market.set_payees(vec![ALICE], vec![10_000]).is_err());
+
+        let eta = U256::from(testing::TIMESTAMP);
+        assert!(market.schedule(data.into(), eta).is_ok());
+        assert!(market.set_payees(vec![ALICE], vec![10_000]).is_ok());
+        assert_eq!(market.payees(), Ok((vec![ALICE], vec![10_000])));
+
+        let log = testing::take_logs().pop().unwrap();
+        assert_eq!(log.topics[0], PayeesUpdated::SIGNATURE_HASH.0);
+    }
+
+    #[test]
+    fn escrowed_proceeds_are_withdrawn_by_each_payee() {
+        let mut market = market();
+        assert!(market
+            .set_payees_internal(vec![SENDER, VAULT], vec![3_333, 6_667])
+            .is_ok());
+        assert!(market.set_escrow_mode(true).is_ok());
+        assert!(market.escrow_mode());
+        testing::take_logs();
+        testing::take_calls();
+
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+
+        // The whole price is kept in the market, and nothing is sent to the payees
+        let escrowed: Vec<(Address, U256)> = testing::take_logs()
+            .iter()
+            .filter(|log| log.topics[0] == Escrowed::SIGNATURE_HASH.0)
+            .map(|log| Escrowed::abi_decode_data(&log.data, true).unwrap())
+            .map(|(_, amount, _, payee)| (payee, amount))
+            .collect();
+        assert_eq!(
+            escrowed,
+            [
+                (SENDER, U256::from(1_111_000)),
+                (VAULT, U256::from(2_222_334))
+            ]
+        );
+        assert_eq!(market.escrowed_balance(TOKEN), U256::from(3_333_334));
+        assert_eq!(market.pending_balance(TOKEN, SENDER), U256::from(1_111_000));
+        assert_eq!(market.pending_balance(TOKEN, VAULT), U256::from(2_222_334));
+        let token_calls = testing::take_calls()
+            .iter()
+            .filter(|call| call.0 == TOKEN)
+            .count();
+        // decimals and a single transferFrom to the market
+        assert_eq!(token_calls, 2);
+
+        assert_eq!(market.withdraw(TOKEN), Ok(U256::from(1_111_000)));
+        assert_eq!(market.pending_balance(TOKEN, SENDER), U256::ZERO);
+        assert_eq!(market.escrowed_balance(TOKEN), U256::from(2_222_334));
+        let log = testing::take_logs().pop().unwrap();
+        assert_eq!(log.topics[0], Withdrawn::SIGNATURE_HASH.0);
+
+        assert_eq!(
+            market.withdraw(TOKEN),
+            Err(NothingToWithdraw {
+                token: TOKEN,
+                payee: SENDER
+            }
+            .abi_encode())
+        );
+    }
+
+    #[test]
+    fn escrow_mode_off_pays_the_payees_again() {
+        let mut market = market();
+        assert!(market.set_escrow_mode(true).is_ok());
+        assert!(market.set_escrow_mode(false).is_ok());
+        testing::take_logs();
+
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+
+        assert_eq!(collected(), [(VAULT, U256::from(3_333_334))]);
+        assert_eq!(market.escrowed_balance(TOKEN), U256::ZERO);
+    }
+
+    #[test]
+    fn withdraw_cannot_be_reentered() {
+        let mut market = market();
+        assert!(market
+            .set_payees_internal(vec![SENDER], vec![10_000])
+            .is_ok());
+        assert!(market.set_escrow_mode(true).is_ok());
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+
+        // As if the token called back into the market while sending the funds
+        market.reentrancy_guard._enter().unwrap();
+        assert_eq!(market.withdraw(TOKEN), Err(ReentrantCall {}.abi_encode()));
+
+        market.reentrancy_guard._exit();
+        assert_eq!(market.withdraw(TOKEN), Ok(U256::from(3_333_334)));
+    }
+
+    /// Schedules the campaign start, and starts it
+    fn scheduled_start(market: &mut Market, soft_cap: U256, deadline: U256) -> Result<(), Vec<u8>> {
+        let data = startCampaignCall {
+            softCap: soft_cap,
+            deadline,
+        }
+        .abi_encode();
+        market.schedule(data.into(), U256::from(testing::TIMESTAMP))?;
+        market.start_campaign(soft_cap, deadline)
+    }
+
+    /// Starts a campaign to raise `soft_cap` USD, open for a day
+    fn start_campaign(market: &mut Market, soft_cap: u64) {
+        let deadline = U256::from(testing::TIMESTAMP + 86_400);
+        assert!(scheduled_start(market, U256::from(soft_cap), deadline).is_ok());
+        token(0);
+        testing::take_logs();
+        testing::take_calls();
+    }
+
+    /// Makes the token keep the balance of the market. It takes `fee` basis points
+    /// of every payment the market receives, like a token with a fee on transfers.
+    fn token(fee: u64) {
+        let balance = Rc::new(RefCell::new(U256::ZERO));
+        testing::on_call(move |to, calldata| {
+            if to != TOKEN {
+                return answer(to, calldata);
+            }
+            match calldata[..4].try_into().unwrap() {
+                transferFromCall::SELECTOR => {
+                    let call = transferFromCall::abi_decode(calldata, true).unwrap();
+                    if call.to == testing::CONTRACT {
+                        let taken = call.value * U256::from(fee) / U256::from(10_000);
+                        *balance.borrow_mut() += call.value - taken;
+                    }
+                    Ok(true.abi_encode())
+                }
+                transferCall::SELECTOR => {
+                    let call = transferCall::abi_decode(calldata, true).unwrap();
+                    *balance.borrow_mut() -= call.value;
+                    Ok(true.abi_encode())
+                }
+                balanceOfCall::SELECTOR => Ok(balance.borrow().abi_encode()),
+                _ => answer(to, calldata),
+            }
+        });
+    }
+
+    /// As if the campaign deadline had passed
+    fn end_campaign(market: &mut Market) {
+        market.campaign_deadline.set(U256::from(testing::TIMESTAMP));
+    }
+
+    #[test]
+    fn campaign_takes_the_payments_until_it_ends() {
+        let mut market = market();
+        start_campaign(&mut market, 30);
+
+        assert!(market.buy(SENDER, USDC_USD, 2).is_ok());
+
+        // The tokens are held by the market, and no NFT is minted yet
+        let targets: Vec<Address> = testing::take_calls().iter().map(|call| call.0).collect();
+        assert!(!targets.contains(&NFT));
+        assert!(collected().is_empty());
+        assert_eq!(
+            market.campaign_contribution(SENDER, TOKEN),
+            U256::from(6_666_668)
+        );
+        assert_eq!(market.campaign_nfts(SENDER), U256::from(2));
+        assert_eq!(market.campaign().3, U256::from(20));
+
+        end_campaign(&mut market);
+        assert_eq!(
+            market.buy(SENDER, USDC_USD, 1),
+            Err(CampaignEnded {}.abi_encode())
+        );
+    }
+
+    #[test]
+    fn campaign_needs_a_soft_cap_and_a_future_deadline() {
+        let mut market = market();
+        let now = U256::from(testing::TIMESTAMP);
+
+        assert_eq!(
+            scheduled_start(&mut market, U256::ZERO, now + U256::from(1)),
+            Err(InvalidCampaign {}.abi_encode())
+        );
+        assert_eq!(
+            scheduled_start(&mut market, U256::from(30), now),
+            Err(InvalidCampaign {}.abi_encode())
+        );
+
+        start_campaign(&mut market, 30);
+        assert_eq!(
+            scheduled_start(&mut market, U256::from(30), now + U256::from(1)),
+            Err(InvalidCampaignState {
+                state: CAMPAIGN_OPEN
+            }
+            .abi_encode())
+        );
+    }
+
+    #[test]
+    fn campaign_start_waits_for_the_timelock() {
+        let mut market = market();
+        let deadline = U256::from(testing::TIMESTAMP + 86_400);
+
+        assert!(market.start_campaign(U256::from(30), deadline).is_err());
+        assert_eq!(market.campaign().0, CAMPAIGN_NONE);
+
+        assert!(scheduled_start(&mut market, U256::from(30), deadline).is_ok());
+        assert_eq!(market.campaign().0, CAMPAIGN_OPEN);
+    }
+
+    #[test]
+    fn campaign_counts_only_the_tokens_received() {
+        let mut market = market();
+        start_campaign(&mut market, 30);
+        // A 1% fee on transfers
+        token(100);
+
+        assert!(market.buy(SENDER, USDC_USD, 3).is_ok());
+
+        // 10_000_002 paid, less the fee, is worth 29.7 USD
+        let received = U256::from(9_900_002);
+        assert_eq!(market.campaign_contribution(SENDER, TOKEN), received);
+        assert_eq!(market.campaign().3, U256::from(29));
+        assert_eq!(
+            market.finalize_campaign(),
+            Err(CampaignNotEnded {}.abi_encode())
+        );
+
+        // And the refund gives back what the market holds
+        end_campaign(&mut market);
+        assert_eq!(market.finalize_campaign(), Ok(false));
+        testing::take_logs();
+        assert!(market.refund().is_ok());
+        let refunded = testing::take_logs()
+            .iter()
+            .find(|log| log.topics[0] == CampaignRefunded::SIGNATURE_HASH.0)
+            .map(|log| CampaignRefunded::abi_decode_data(&log.data, true).unwrap())
+            .unwrap();
+        assert_eq!(refunded, (SENDER, TOKEN, received));
+    }
+
+    #[test]
+    fn successful_campaign_escrows_the_funds_and_lets_buyers_claim() {
+        let mut market = market();
+        start_campaign(&mut market, 30);
+        assert!(market.buy(SENDER, USDC_USD, 3).is_ok());
+
+        // The soft cap is met, so it can end before the deadline
+        assert_eq!(market.finalize_campaign(), Ok(true));
+        assert_eq!(market.campaign().0, CAMPAIGN_SUCCEEDED);
+        assert_eq!(market.pending_balance(TOKEN, VAULT), U256::from(10_000_002));
+        assert_eq!(market.escrowed_balance(TOKEN), U256::from(10_000_002));
+        testing::take_logs();
+
+        assert!(market.claim().is_ok());
+        let claimed = testing::take_logs()
+            .iter()
+            .find(|log| log.topics[0] == CampaignClaimed::SIGNATURE_HASH.0)
+            .map(|log| CampaignClaimed::abi_decode_data(&log.data, true).unwrap())
+            .unwrap();
+        assert_eq!(claimed, (SENDER, U256::from(7), U256::from(3)));
+        assert_eq!(
+            market.claim(),
+            Err(NothingToClaim { buyer: SENDER }.abi_encode())
+        );
+        // The revert would release the guard, which the test host doesn't roll back
+        market.reentrancy_guard._exit();
+
+        // And the sales go back to normal
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+        assert_eq!(collected(), [(VAULT, U256::from(3_333_334))]);
+    }
+
+    #[test]
+    fn failed_campaign_refunds_the_buyers_and_closes_the_sales() {
+        let mut market = market();
+        start_campaign(&mut market, 30);
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+
+        // Not met, so it waits for the deadline
+        assert_eq!(
+            market.finalize_campaign(),
+            Err(CampaignNotEnded {}.abi_encode())
+        );
+        end_campaign(&mut market);
+        assert_eq!(market.finalize_campaign(), Ok(false));
+        assert_eq!(
+            market.claim(),
+            Err(InvalidCampaignState {
+                state: CAMPAIGN_FAILED
+            }
+            .abi_encode())
+        );
+        market.reentrancy_guard._exit();
+        testing::take_logs();
+
+        assert!(market.refund().is_ok());
+        let refunded = testing::take_logs()
+            .iter()
+            .find(|log| log.topics[0] == CampaignRefunded::SIGNATURE_HASH.0)
+            .map(|log| CampaignRefunded::abi_decode_data(&log.data, true).unwrap())
+            .unwrap();
+        assert_eq!(refunded, (SENDER, TOKEN, U256::from(3_333_334)));
+        assert_eq!(market.campaign_nfts(SENDER), U256::ZERO);
+        assert_eq!(
+            market.refund(),
+            Err(NothingToRefund { buyer: SENDER }.abi_encode())
+        );
+        market.reentrancy_guard._exit();
+
+        assert_eq!(
+            market.buy(SENDER, USDC_USD, 1),
+            Err(InvalidCampaignState {
+                state: CAMPAIGN_FAILED
+            }
+            .abi_encode())
+        );
+    }
+
+    #[test]
+    fn claim_and_refund_cannot_be_reentered() {
+        let mut market = market();
+        start_campaign(&mut market, 10);
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+        assert_eq!(market.finalize_campaign(), Ok(true));
+
+        // As if the NFT or the token called back into the market
+        market.reentrancy_guard._enter().unwrap();
+        assert_eq!(market.claim(), Err(ReentrantCall {}.abi_encode()));
+        assert_eq!(market.refund(), Err(ReentrantCall {}.abi_encode()));
+
+        market.reentrancy_guard._exit();
+        assert!(market.claim().is_ok());
+    }
+
+    /// Records the batches the NFT contract mints, with a supply that grows with them
+    fn mint_batches() -> Rc<RefCell<Vec<u32>>> {
+        let batches = Rc::new(RefCell::new(vec![]));
+        let minted = batches.clone();
+        testing::on_call(move |to, calldata| {
+            if to != NFT {
+                return answer(to, calldata);
+            }
+            match calldata[..4].try_into().unwrap() {
+                totalSupplyCall::SELECTOR => {
+                    let supply: u32 = minted.borrow().iter().sum();
+                    Ok(U256::from(supply).abi_encode())
+                }
+                mintBatchCall::SELECTOR => {
+                    let call = mintBatchCall::abi_decode(calldata, true).unwrap();
+                    // The same bound the Seabrick mintBatch enforces
+                    if call.amount > MAX_CLAIM_BATCH {
+                        return Err(vec![]);
+                    }
+                    minted.borrow_mut().push(call.amount);
+                    Ok(vec![])
+                }
+                _ => answer(to, calldata),
+            }
+        });
+        batches
+    }
+
+    /// A market after a successful campaign, where the test sender is owed `owed` NFTs
+    fn owed(owed: U256) -> Market {
+        let mut market = market();
+        market.campaign_state.set(U8::from(CAMPAIGN_SUCCEEDED));
+        market.campaign_nfts.setter(SENDER).set(owed);
+        market
+    }
+
+    #[test]
+    fn claim_larger_than_a_batch_is_minted_over_several_calls() {
+        let mut market = owed(U256::from(MAX_CLAIM_BATCH * 2 + 7));
+        let batches = mint_batches();
+
+        assert!(market.claim().is_ok());
+        assert_eq!(
+            market.campaign_nfts(SENDER),
+            U256::from(MAX_CLAIM_BATCH + 7)
+        );
+
+        assert!(market.claim().is_ok());
+        assert!(market.claim().is_ok());
+
+        assert_eq!(*batches.borrow(), [MAX_CLAIM_BATCH, MAX_CLAIM_BATCH, 7]);
+        assert_eq!(market.campaign_nfts(SENDER), U256::ZERO);
+        assert!(market.claim().is_err());
+        market.reentrancy_guard._exit();
+    }
+
+    #[test]
+    fn claim_beyond_u32_is_bounded() {
+        let mut market = owed(U256::MAX);
+        let batches = mint_batches();
+
+        assert!(market.claim().is_ok());
+
+        assert_eq!(*batches.borrow(), [MAX_CLAIM_BATCH]);
+        assert_eq!(
+            market.campaign_nfts(SENDER),
+            U256::MAX - U256::from(MAX_CLAIM_BATCH)
+        );
+    }
+
+    /// Turns on a cooling-off period of a day. The NFT contract records the tokens
+    /// burned through `burnFrom`, where the market acts as a burner.
+    fn cooling_off(market: &mut Market) -> Rc<RefCell<Vec<(Address, U256)>>> {
+        let period = U256::from(86_400);
+        let data = setCoolingOffPeriodCall { period }.abi_encode();
+        assert!(market
+            .schedule(data.into(), U256::from(testing::TIMESTAMP))
+            .is_ok());
+        assert!(market.set_cooling_off_period(period).is_ok());
+
+        let burns = Rc::new(RefCell::new(vec![]));
+        let burned = burns.clone();
+        testing::on_call(move |to, calldata| {
+            if to == NFT && calldata.starts_with(&burnFromCall::SELECTOR) {
+                let call = burnFromCall::abi_decode(calldata, true).unwrap();
+                burned.borrow_mut().push((call.from, call.token_id));
+                return Ok(vec![]);
+            }
+            answer(to, calldata)
+        });
+        testing::take_logs();
+        burns
+    }
+
+    #[test]
+    fn cooling_off_refund_burns_the_token_and_pays_back() {
+        let mut market = market();
+        let burns = cooling_off(&mut market);
+
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+
+        // The payment is held by the market, not sent to the payees
+        assert!(collected().is_empty());
+        let until = U256::from(testing::TIMESTAMP + 86_400);
+        assert_eq!(
+            market.purchase(U256::from(1)),
+            (SENDER, TOKEN, U256::from(3_333_334), until)
+        );
+
+        assert_eq!(
+            market.refund_purchase(U256::from(1)),
+            Ok(U256::from(3_333_334))
+        );
+        assert_eq!(*burns.borrow(), [(SENDER, U256::from(1))]);
+        let log = testing::take_logs().pop().unwrap();
+        assert_eq!(log.topics[0], Refunded::SIGNATURE_HASH.0);
+        assert_eq!(
+            market.purchase(U256::from(1)),
+            (Address::ZERO, Address::ZERO, U256::ZERO, U256::ZERO)
+        );
+
+        assert_eq!(
+            market.refund_purchase(U256::from(1)),
+            Err(UnknownPurchase {
+                tokenId: U256::from(1)
+            }
+            .abi_encode())
+        );
+    }
+
+    #[test]
+    fn refund_fails_if_the_market_cannot_burn() {
+        let mut market = market();
+        cooling_off(&mut market);
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+
+        // The market is not a burner of the NFT contract
+        testing::on_call(|to, calldata| match to {
+            NFT => Err(vec![]),
+            _ => answer(to, calldata),
+        });
+
+        assert!(market.refund_purchase(U256::from(1)).is_err());
+    }
+
+    #[test]
+    fn payments_are_released_after_the_cooling_off_period() {
+        let mut market = market();
+        let burns = cooling_off(&mut market);
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+
+        assert_eq!(
+            market.release_purchases(vec![U256::from(1)]),
+            Err(StillRefundable {
+                tokenId: U256::from(1)
+            }
+            .abi_encode())
+        );
+
+        // As if the cooling-off period was over
+        market
+            .purchases
+            .setter(U256::from(1))
+            .refundable_until
+            .set(U256::from(testing::TIMESTAMP));
+
+        assert_eq!(
+            market.refund_purchase(U256::from(1)),
+            Err(NotRefundable {
+                tokenId: U256::from(1)
+            }
+            .abi_encode())
+        );
+        market.reentrancy_guard._exit();
+        assert!(burns.borrow().is_empty());
+
+        assert!(market.release_purchases(vec![U256::from(1)]).is_ok());
+        assert_eq!(market.pending_balance(TOKEN, VAULT), U256::from(3_333_334));
+        assert_eq!(
+            market.release_purchases(vec![U256::from(1)]),
+            Err(UnknownPurchase {
+                tokenId: U256::from(1)
+            }
+            .abi_encode())
+        );
+    }
+
+    /// Sets a fixed `rate` for the token of [`USDC_USD`], once scheduled
+    fn set_fixed_rate(market: &mut Market, rate: u64) -> Result<(), Vec<u8>> {
+        let rate = U256::from(rate);
+        let data = setFixedRateCall {
+            name: USDC_USD,
+            token: TOKEN,
+            rate,
+        }
+        .abi_encode();
+        assert!(market
+            .schedule(data.into(), U256::from(testing::TIMESTAMP))
+            .is_ok());
+        market.set_fixed_rate(USDC_USD, TOKEN, rate)
+    }
+
+    #[test]
+    fn fixed_rate_feeds_price_without_the_oracle() {
+        let mut market = market();
+        assert!(set_fixed_rate(&mut market, 100_000_000).is_ok());
+        assert_eq!(
+            market.price_feed(USDC_USD),
+            (Address::ZERO, TOKEN, true, U256::from(100_000_000))
+        );
+        testing::take_logs();
+        testing::take_calls();
+
+        assert!(market.buy(SENDER, USDC_USD, 1).is_ok());
+
+        // 10 USD at 1 USD per token, with 6 decimals
+        assert_eq!(collected(), [(VAULT, U256::from(10_000_000))]);
+        let targets: Vec<Address> = testing::take_calls().iter().map(|call| call.0).collect();
+        assert!(!targets.contains(&FEED));
+    }
+
+    #[test]
+    fn fixed_rates_must_not_be_zero() {
+        let mut market = market();
+
+        assert_eq!(
+            set_fixed_rate(&mut market, 0),
+            Err(ZeroRate {}.abi_encode())
+        );
+        assert_eq!(
+            market.price_feed(USDC_USD),
+            (FEED, TOKEN, false, U256::ZERO)
+        );
+    }
+
+    #[test]
+    fn setting_an_oracle_replaces_the_fixed_rate() {
+        let mut market = market();
+        assert!(set_fixed_rate(&mut market, 100_000_000).is_ok());
+
+        assert!(market
+            .set_aggregators_internal(vec![USDC_USD], vec![FEED], vec![TOKEN])
+            .is_ok());
+
+        assert_eq!(
+            market.price_feed(USDC_USD),
+            (FEED, TOKEN, false, U256::ZERO)
+        );
+    }
 }
@@ -23,11 +23,25 @@ pub trait Erc721Params {
     fn token_uri(token_id: U256) -> String;
 }
 
+/// Maximum amount of tokens that can be minted in a single batch.
+///
+/// Ownership is resolved by scanning back to the start of the batch, so this bounds
+/// the amount of storage reads needed by [`Erc721::owner_of`]. Each one is a cold
+/// read of 2100 gas, which keeps the worst lookup (and any transfer or burn built
+/// on it) around half a million gas. Larger amounts are minted over several batches.
+pub const MAX_BATCH_SIZE: u32 = 250;
+
 sol_storage! {
     /// Erc721 implements all ERC-721 methods
+    ///
+    /// Ownership uses a lazy (ERC721A-style) layout: a batch mint only stores the owner
+    /// of the first token, and the owner of any other token is resolved by scanning back
+    /// to the closest initialized slot.
     pub struct Erc721<T: Erc721Params> {
-        /// Token id to owner map
+        /// Token id to owner map. Only initialized at the start of each ownership run
         mapping(uint256 => address) owners;
+        /// Token id to burned status map
+        mapping(uint256 => bool) burned;
         /// User to balance map
         mapping(address => uint256) balances;
         /// Token id to approved user map
@@ -55,6 +69,8 @@ sol! {
     error NotApproved(address owner, address spender, uint256 token_id);
     // Attempt to transfer token id to the Zero address
     error TransferToZero(uint256 token_id);
+    // Attempt to mint more tokens than allowed in a single batch
+    error InvalidBatchSize(uint256 amount, uint256 max);
 }
 
 /// Represents the ways methods may fail.
@@ -64,13 +80,14 @@ pub enum Erc721Error {
     NotOwner(NotOwner),
     NotApproved(NotApproved),
     TransferToZero(TransferToZero),
+    InvalidBatchSize(InvalidBatchSize),
 }
 
 // These methods aren't external, but are helpers used by external methods.
 // Methods marked as "pub" here are usable outside of the erc721 module (i.e. they're callable from lib.rs).
 impl<T: Erc721Params> Erc721<T> {
     /// Requires that msg::sender() is authorized to spend a given token
-    fn require_authorized_to_spend(
+    pub fn require_authorized_to_spend(
         &self,
         from: Address,
         token_id: U256,
@@ -117,8 +134,7 @@ impl<T: Erc721Params> Erc721<T> {
         from: Address,
         to: Address,
     ) -> Result<(), Erc721Error> {
-        let mut owner = self.owners.setter(token_id);
-        let previous_owner = owner.get();
+        let previous_owner = self.owner_of(token_id)?;
         if previous_owner != from {
             return Err(Erc721Error::NotOwner(NotOwner {
                 from,
@@ -126,16 +142,26 @@ impl<T: Erc721Params> Erc721<T> {
                 real_owner: previous_owner,
             }));
         }
-        owner.set(to);
+        self.owners.setter(token_id).set(to);
+
+        // Transferring to the zero address burns the token
+        if to.is_zero() {
+            self.burned.setter(token_id).set(true);
+        }
+
+        // The next token could be relying on this slot to resolve its owner
+        self.initialize_next_slot(token_id, from);
 
         // right now working with storage can be verbose, but this will change upcoming version of the Stylus SDK
         let mut from_balance = self.balances.setter(from);
         let balance = from_balance.get() - U256::from(1);
         from_balance.set(balance);
 
-        let mut to_balance = self.balances.setter(to);
-        let balance = to_balance.get() + U256::from(1);
-        to_balance.set(balance);
+        if !to.is_zero() {
+            let mut to_balance = self.balances.setter(to);
+            let balance = to_balance.get() + U256::from(1);
+            to_balance.set(balance);
+        }
 
         // cleaning app the approved mapping for this token
         self.token_approvals.delete(token_id);
@@ -144,11 +170,69 @@ impl<T: Erc721Params> Erc721<T> {
         Ok(())
     }
 
+    /// Stores `owner` in the slot following `token_id` if that token exists and is
+    /// currently resolved by scanning back, so it keeps its owner after `token_id` moves.
+    fn initialize_next_slot(&mut self, token_id: U256, owner: Address) {
+        let next_token_id = token_id + U256::from(1u8);
+
+        if next_token_id >= self.total_supply.get()
+            || !self.owners.get(next_token_id).is_zero()
+            || self.burned.get(next_token_id)
+        {
+            return;
+        }
+
+        self.owners.setter(next_token_id).set(owner);
+    }
+
     /// Mints a new token and transfers it to `to`
     pub fn mint(&mut self, to: Address) -> Result<(), Erc721Error> {
-        let new_token_id = self.total_supply.get();
-        self.total_supply.set(new_token_id + U256::from(1u8));
-        self.transfer(new_token_id, Address::default(), to)?;
+        self.mint_batch(to, 1)
+    }
+
+    /// Mints `amount` consecutive tokens to `to`.
+    /// Only the owner of the first token is written, the rest are resolved lazily.
+    pub fn mint_batch(&mut self, to: Address, amount: u32) -> Result<(), Erc721Error> {
+        let start_token_id = self.total_supply.get();
+
+        // A zero owner slot would make the batch resolve to the previous owner.
+        // Checked even for an empty batch, so a zero recipient is never accepted
+        if to.is_zero() {
+            return Err(Erc721Error::TransferToZero(TransferToZero {
+                token_id: start_token_id,
+            }));
+        }
+
+        if amount == 0 {
+            return Ok(());
+        }
+
+        if amount > MAX_BATCH_SIZE {
+            return Err(Erc721Error::InvalidBatchSize(InvalidBatchSize {
+                amount: U256::from(amount),
+                max: U256::from(MAX_BATCH_SIZE),
+            }));
+        }
+
+        let amount = U256::from(amount);
+        self.total_supply.set(start_token_id + amount);
+
+        self.owners.setter(start_token_id).set(to);
+
+        let mut to_balance = self.balances.setter(to);
+        let balance = to_balance.get() + amount;
+        to_balance.set(balance);
+
+        let mut token_id = start_token_id;
+        while token_id < start_token_id + amount {
+            evm::log(Transfer {
+                from: Address::default(),
+                to,
+                token_id,
+            });
+            token_id += U256::from(1u8);
+        }
+
         Ok(())
     }
 
@@ -190,12 +274,21 @@ impl<T: Erc721Params> Erc721<T> {
     }
 
     /// Gets the owner of the NFT, if it exists.
+    /// The owner is the one stored in the closest initialized slot at or before `token_id`.
     pub fn owner_of(&self, token_id: U256) -> Result<Address, Erc721Error> {
-        let owner = self.owners.get(token_id);
-        if owner.is_zero() {
+        if token_id >= self.total_supply.get() || self.burned.get(token_id) {
             return Err(Erc721Error::InvalidTokenId(InvalidTokenId { token_id }));
         }
-        Ok(owner)
+
+        // The start of every batch is always initialized, so this ends before underflowing
+        let mut current = token_id;
+        loop {
+            let owner = self.owners.get(current);
+            if !owner.is_zero() {
+                return Ok(owner);
+            }
+            current -= U256::from(1u8);
+        }
     }
 
     /// Transfers the NFT.
@@ -308,3 +401,202 @@ impl<T: Erc721Params> Erc721<T> {
         ))
     }
 }
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+    use seabrick_common::testing;
+
+    struct Params;
+
+    impl Erc721Params for Params {
+        const NAME: &'static str = "Test";
+        const SYMBOL: &'static str = "TEST";
+
+        fn token_uri(_token_id: U256) -> String {
+            String::new()
+        }
+    }
+
+    const ALICE: Address = address!("000000000000000000000000000000000000a11c");
+    const BOB: Address = address!("0000000000000000000000000000000000000b0b");
+
+    fn erc721() -> Erc721<Params> {
+        testing::reset();
+        unsafe { Erc721::new(U256::ZERO, 0) }
+    }
+
+    fn owner(erc721: &Erc721<Params>, token_id: u32) -> Option<Address> {
+        erc721.owner_of(U256::from(token_id)).ok()
+    }
+
+    fn balance(erc721: &Erc721<Params>, account: Address) -> U256 {
+        erc721.balance_of(account).ok().unwrap()
+    }
+
+    fn transfer(erc721: &mut Erc721<Params>, token_id: u32, from: Address, to: Address) {
+        assert!(erc721.transfer(U256::from(token_id), from, to).is_ok());
+    }
+
+    #[test]
+    fn batches_resolve_every_owner() {
+        let mut erc721 = erc721();
+        assert!(erc721.mint_batch(ALICE, 3).is_ok());
+        assert!(erc721.mint_batch(BOB, 2).is_ok());
+
+        for token_id in 0..3 {
+            assert_eq!(owner(&erc721, token_id), Some(ALICE));
+        }
+        for token_id in 3..5 {
+            assert_eq!(owner(&erc721, token_id), Some(BOB));
+        }
+        assert_eq!(owner(&erc721, 5), None);
+        assert_eq!(balance(&erc721, ALICE), U256::from(3));
+        assert_eq!(balance(&erc721, BOB), U256::from(2));
+    }
+
+    #[test]
+    fn transfer_mid_batch_keeps_the_rest_of_the_batch() {
+        let mut erc721 = erc721();
+        assert!(erc721.mint_batch(ALICE, 5).is_ok());
+
+        transfer(&mut erc721, 2, ALICE, BOB);
+
+        assert_eq!(owner(&erc721, 1), Some(ALICE));
+        assert_eq!(owner(&erc721, 2), Some(BOB));
+        assert_eq!(owner(&erc721, 3), Some(ALICE));
+        assert_eq!(owner(&erc721, 4), Some(ALICE));
+        assert_eq!(balance(&erc721, ALICE), U256::from(4));
+        assert_eq!(balance(&erc721, BOB), U256::from(1));
+
+        // Moving it back doesn't change the tokens around it either
+        transfer(&mut erc721, 2, BOB, ALICE);
+
+        for token_id in 0..5 {
+            assert_eq!(owner(&erc721, token_id), Some(ALICE));
+        }
+        assert_eq!(balance(&erc721, BOB), U256::ZERO);
+    }
+
+    #[test]
+    fn transfer_of_the_batch_start_keeps_the_rest_of_the_batch() {
+        let mut erc721 = erc721();
+        assert!(erc721.mint_batch(ALICE, 3).is_ok());
+
+        transfer(&mut erc721, 0, ALICE, BOB);
+
+        assert_eq!(owner(&erc721, 0), Some(BOB));
+        assert_eq!(owner(&erc721, 1), Some(ALICE));
+        assert_eq!(owner(&erc721, 2), Some(ALICE));
+    }
+
+    #[test]
+    fn transfer_from_a_wrong_owner_fails() {
+        let mut erc721 = erc721();
+        assert!(erc721.mint_batch(ALICE, 3).is_ok());
+
+        assert!(matches!(
+            erc721.transfer(U256::from(1), BOB, ALICE),
+            Err(Erc721Error::NotOwner(_))
+        ));
+    }
+
+    #[test]
+    fn burn_mid_batch_keeps_the_tokens_after_it() {
+        let mut erc721 = erc721();
+        assert!(erc721.mint_batch(ALICE, 5).is_ok());
+
+        assert!(erc721.burn(ALICE, U256::from(2)).is_ok());
+
+        assert_eq!(owner(&erc721, 1), Some(ALICE));
+        assert_eq!(owner(&erc721, 2), None);
+        assert_eq!(owner(&erc721, 3), Some(ALICE));
+        assert_eq!(owner(&erc721, 4), Some(ALICE));
+        assert_eq!(balance(&erc721, ALICE), U256::from(4));
+
+        // A burned token can't be burned or moved again
+        assert!(erc721.burn(ALICE, U256::from(2)).is_err());
+        assert!(erc721.transfer(U256::from(2), ALICE, BOB).is_err());
+    }
+
+    #[test]
+    fn tokens_after_consecutive_burns_resolve_past_them() {
+        let mut erc721 = erc721();
+        assert!(erc721.mint_batch(ALICE, 6).is_ok());
+
+        // Burn tokens 1 and 2, then move 3, so tokens 4 and 5 must be resolved
+        // through the slots left by the burns
+        assert!(erc721.burn(ALICE, U256::from(1)).is_ok());
+        assert!(erc721.burn(ALICE, U256::from(2)).is_ok());
+        transfer(&mut erc721, 3, ALICE, BOB);
+
+        assert_eq!(owner(&erc721, 0), Some(ALICE));
+        assert_eq!(owner(&erc721, 1), None);
+        assert_eq!(owner(&erc721, 2), None);
+        assert_eq!(owner(&erc721, 3), Some(BOB));
+        assert_eq!(owner(&erc721, 4), Some(ALICE));
+        assert_eq!(owner(&erc721, 5), Some(ALICE));
+        assert_eq!(balance(&erc721, ALICE), U256::from(3));
+    }
+
+    #[test]
+    fn burn_at_the_end_of_a_batch_then_mint() {
+        let mut erc721 = erc721();
+        assert!(erc721.mint_batch(ALICE, 3).is_ok());
+
+        assert!(erc721.burn(ALICE, U256::from(2)).is_ok());
+        assert!(erc721.mint_batch(BOB, 2).is_ok());
+
+        assert_eq!(owner(&erc721, 1), Some(ALICE));
+        assert_eq!(owner(&erc721, 2), None);
+        assert_eq!(owner(&erc721, 3), Some(BOB));
+        assert_eq!(owner(&erc721, 4), Some(BOB));
+        assert_eq!(balance(&erc721, ALICE), U256::from(2));
+        assert_eq!(balance(&erc721, BOB), U256::from(2));
+    }
+
+    #[test]
+    fn burn_at_the_end_of_a_batch_keeps_the_next_batch() {
+        let mut erc721 = erc721();
+        assert!(erc721.mint_batch(ALICE, 3).is_ok());
+        assert!(erc721.mint_batch(BOB, 3).is_ok());
+
+        assert!(erc721.burn(ALICE, U256::from(2)).is_ok());
+        transfer(&mut erc721, 1, ALICE, BOB);
+
+        assert_eq!(owner(&erc721, 0), Some(ALICE));
+        assert_eq!(owner(&erc721, 1), Some(BOB));
+        assert_eq!(owner(&erc721, 2), None);
+        for token_id in 3..6 {
+            assert_eq!(owner(&erc721, token_id), Some(BOB));
+        }
+    }
+
+    #[test]
+    fn batch_size_is_bounded() {
+        let mut erc721 = erc721();
+
+        assert!(matches!(
+            erc721.mint_batch(ALICE, MAX_BATCH_SIZE + 1),
+            Err(Erc721Error::InvalidBatchSize(_))
+        ));
+        assert!(erc721.mint_batch(ALICE, MAX_BATCH_SIZE).is_ok());
+        assert_eq!(owner(&erc721, MAX_BATCH_SIZE - 1), Some(ALICE));
+    }
+
+    #[test]
+    fn zero_recipient_is_rejected_even_for_empty_batches() {
+        let mut erc721 = erc721();
+
+        assert!(matches!(
+            erc721.mint_batch(Address::ZERO, 0),
+            Err(Erc721Error::TransferToZero(_))
+        ));
+        assert!(matches!(
+            erc721.mint_batch(Address::ZERO, 2),
+            Err(Erc721Error::TransferToZero(_))
+        ));
+        assert_eq!(erc721.total_supply.get(), U256::ZERO);
+    }
+}
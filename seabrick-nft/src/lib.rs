@@ -1,18 +1,22 @@
-#![cfg_attr(not(feature = "export-abi"), no_std, no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(feature = "export-abi"), no_std)]
 
 extern crate alloc;
 
 mod erc721;
-mod initialization;
-mod ownable;
 
 use alloc::{format, string::String, vec::Vec};
-use alloy_sol_types::sol;
+use alloy_sol_types::{sol, SolCall};
 use erc721::{Erc721, Erc721Error, Erc721Params};
-use initialization::{Initialization, InitializationError};
-use ownable::Ownable;
+use seabrick_common::{
+    initialization::Initialization,
+    ownable::{roles::MINTER_ADMIN, Ownable},
+    timelock::Timelock,
+};
 use stylus_sdk::{
-    alloy_primitives::{Address, U256},
+    abi::Bytes,
+    alloy_primitives::{Address, FixedBytes, U256},
+    crypto::keccak,
     evm, msg,
     prelude::{entrypoint, public, sol_storage, SolidityError},
 };
@@ -44,7 +48,16 @@ sol_storage! {
         Ownable ownable;
         #[borrow]
         Initialization init;
-        mapping(address => bool) minters;
+        /// Minter to remaining amount of tokens it's allowed to mint
+        mapping(address => uint256) minter_allowances;
+        /// Addresses with a non-zero allowance
+        address[] minter_list;
+        /// Minter to its position in `minter_list` plus one (zero means not a minter)
+        mapping(address => uint256) minter_positions;
+        /// Addresses allowed to burn any token without an approval (eg. the market, for refunds)
+        mapping(address => bool) burners;
+        #[borrow]
+        Timelock timelock;
     }
 }
 
@@ -52,31 +65,170 @@ sol! {
     /// Seabrick Details
     event SeabrickDetails(address ownershipContract);
 
-    event MinterUpdated(address minter, bool status);
+    /// Emitted when the allowance of a minter changes, carrying the remaining quota
+    event MinterUpdated(address minter, uint256 allowance);
+
+    /// Emitted when a burner is granted or revoked
+    event BurnerUpdated(address burner, bool allowed);
+
+    /// Emitted when a token is burned, either by its owner, an approved operator or a burner
+    event Burned(address indexed from, address indexed operator, uint256 indexed token_id);
 
     error OnlyMinters();
+
+    /// The minter does not have enough allowance to mint the requested amount
+    error MinterAllowanceExceeded(address minter, uint256 allowance, uint256 amount);
+
+    /// Mismatch on recipients and amounts provided
+    error MismatchRecipients();
+}
+
+sol! {
+    /// Timelocked calls. Used to build the calldata that must be scheduled
+    function grantMinter(address minter, uint256 allowance);
+    function increaseMinterAllowance(address minter, uint256 amount);
+    function grantBurner(address burner);
+    function setMinDelay(uint256 delay);
 }
 
 #[derive(SolidityError)]
 pub enum SeabrickError {
     OnlyMinters(OnlyMinters),
+    MismatchRecipients(MismatchRecipients),
+    MinterAllowanceExceeded(MinterAllowanceExceeded),
+}
+
+impl Seabrick {
+    /// Checks that the caller can schedule or cancel the call `data`,
+    /// which requires the same role needed to execute it
+    pub fn only_scheduler(&self, data: &[u8]) -> Result<(), Vec<u8>> {
+        let selector: [u8; 4] = data
+            .get(..4)
+            .and_then(|selector| selector.try_into().ok())
+            .unwrap_or_default();
+
+        match selector {
+            grantMinterCall::SELECTOR
+            | increaseMinterAllowanceCall::SELECTOR
+            | grantBurnerCall::SELECTOR => self.ownable.only_role(MINTER_ADMIN),
+            _ => self.ownable.only_owner(),
+        }
+    }
+
+    /// Consumes `amount` from the allowance of [`msg::sender`]
+    pub fn spend_minter_allowance(&mut self, amount: U256) -> Result<(), SeabrickError> {
+        let minter = msg::sender();
+        let allowance = self.minter_allowances.get(minter);
+
+        if allowance.is_zero() {
+            return Err(SeabrickError::OnlyMinters(OnlyMinters {}));
+        }
+
+        if allowance < amount {
+            return Err(SeabrickError::MinterAllowanceExceeded(
+                MinterAllowanceExceeded {
+                    minter,
+                    allowance,
+                    amount,
+                },
+            ));
+        }
+
+        self.set_minter_allowance(minter, allowance - amount);
+
+        Ok(())
+    }
+
+    /// Burns `token_id` from `from` if [`msg::sender`] is allowed to spend it.
+    /// Burners don't need an approval, but `from` must still be the owner.
+    pub fn burn_internal(&mut self, from: Address, token_id: U256) -> Result<(), Erc721Error> {
+        if !self.burners.get(msg::sender()) {
+            self.erc721.require_authorized_to_spend(from, token_id)?;
+        }
+        self.erc721.burn(from, token_id)?;
+
+        evm::log(Burned {
+            from,
+            operator: msg::sender(),
+            token_id,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_burner(&mut self, burner: Address, allowed: bool) {
+        self.burners.insert(burner, allowed);
+
+        evm::log(BurnerUpdated { burner, allowed });
+    }
+
+    pub fn set_minter_allowance(&mut self, minter: Address, allowance: U256) {
+        self.minter_allowances.setter(minter).set(allowance);
+
+        if allowance.is_zero() {
+            self.remove_minter(minter);
+        } else {
+            self.add_minter(minter);
+        }
+
+        evm::log(MinterUpdated { minter, allowance });
+    }
+
+    /// Adds `minter` to the minter list if it's not already there
+    fn add_minter(&mut self, minter: Address) {
+        if !self.minter_positions.get(minter).is_zero() {
+            return;
+        }
+
+        self.minter_list.push(minter);
+        self.minter_positions
+            .setter(minter)
+            .set(U256::from(self.minter_list.len()));
+    }
+
+    /// Removes `minter` from the minter list by swapping it with the last one
+    fn remove_minter(&mut self, minter: Address) {
+        let position = self.minter_positions.get(minter);
+        if position.is_zero() {
+            return;
+        }
+
+        let index = position - U256::from(1u8);
+        let last_index = self.minter_list.len() - 1;
+
+        if let Some(last_minter) = self.minter_list.get(last_index) {
+            if index != U256::from(last_index) {
+                if let Some(mut slot) = self.minter_list.setter(index) {
+                    slot.set(last_minter);
+                }
+                self.minter_positions.setter(last_minter).set(position);
+            }
+        }
+
+        self.minter_list.erase_last();
+        self.minter_positions.delete(minter);
+    }
 }
 
 #[public]
-#[inherit(Erc721<SeabrickParams>, Ownable)]
+#[inherit(Erc721<SeabrickParams>, Initialization, Ownable, Timelock)]
 impl Seabrick {
     pub fn initialization(
         &mut self,
         ownership_contract: Address,
-    ) -> Result<(), InitializationError> {
+        min_delay: U256,
+    ) -> Result<(), Vec<u8>> {
         // Check if already init. Revert if already init
         self.init._check_init()?;
 
         // Set contract ownership contract address
-        self.ownable.set_ownership_contract(ownership_contract);
+        self.ownable.set_ownership_contract(ownership_contract)?;
+
+        // Set the minimum delay for the timelocked owner calls
+        self.timelock._set_min_delay(min_delay);
 
         // Change contract state to already initialized
-        self.init._set_init(true);
+        self.init._set_initialized(1);
 
         evm::log(SeabrickDetails {
             ownershipContract: ownership_contract,
@@ -85,38 +237,781 @@ impl Seabrick {
         Ok(())
     }
 
-    pub fn set_minter(&mut self, minter: Address, status: bool) -> Result<(), Vec<u8>> {
+    /// Sets the amount of tokens that `minter` is allowed to mint. This call is timelocked.
+    pub fn grant_minter(&mut self, minter: Address, allowance: U256) -> Result<(), Vec<u8>> {
+        self.ownable.only_role(MINTER_ADMIN)?;
+        self.timelock
+            ._consume(&grantMinterCall { minter, allowance }.abi_encode())?;
+        self.set_minter_allowance(minter, allowance);
+
+        Ok(())
+    }
+
+    /// Schedules an owner call (`data` is the full calldata) to be executable from `eta`.
+    /// `eta` must be at least `min_delay` seconds from now.
+    /// It requires the same role needed to execute the call.
+    pub fn schedule(&mut self, data: Bytes, eta: U256) -> Result<FixedBytes<32>, Vec<u8>> {
+        self.only_scheduler(&data)?;
+
+        Ok(self.timelock._schedule(&data, eta)?)
+    }
+
+    /// Cancels the scheduled call `data`
+    pub fn cancel(&mut self, data: Bytes) -> Result<(), Vec<u8>> {
+        self.only_scheduler(&data)?;
+        self.timelock._cancel(keccak(&data))?;
+
+        Ok(())
+    }
+
+    /// Changes the minimum delay for scheduled calls. This call is timelocked as well.
+    pub fn set_min_delay(&mut self, delay: U256) -> Result<(), Vec<u8>> {
         self.ownable.only_owner()?;
-        self.minters.setter(minter).set(status);
+        self.timelock
+            ._consume(&setMinDelayCall { delay }.abi_encode())?;
+
+        self.timelock._set_min_delay(delay);
+
+        Ok(())
+    }
+
+    /// Removes any remaining allowance from `minter`.
+    /// Revoking is not timelocked so a compromised minter can be stopped right away.
+    pub fn revoke_minter(&mut self, minter: Address) -> Result<(), Vec<u8>> {
+        self.ownable.only_role(MINTER_ADMIN)?;
+        self.set_minter_allowance(minter, U256::ZERO);
+
+        Ok(())
+    }
+
+    /// Adds `amount` to the current allowance of `minter`. This call is timelocked.
+    pub fn increase_minter_allowance(
+        &mut self,
+        minter: Address,
+        amount: U256,
+    ) -> Result<(), Vec<u8>> {
+        self.ownable.only_role(MINTER_ADMIN)?;
+        self.timelock
+            ._consume(&increaseMinterAllowanceCall { minter, amount }.abi_encode())?;
 
-        evm::log(MinterUpdated { minter, status });
+        let allowance = self.minter_allowances.get(minter);
+        self.set_minter_allowance(minter, allowance.saturating_add(amount));
 
         Ok(())
     }
 
+    /// Allows `burner` to burn any token without an approval. This call is timelocked.
+    pub fn grant_burner(&mut self, burner: Address) -> Result<(), Vec<u8>> {
+        self.ownable.only_role(MINTER_ADMIN)?;
+        self.timelock
+            ._consume(&grantBurnerCall { burner }.abi_encode())?;
+        self.set_burner(burner, true);
+
+        Ok(())
+    }
+
+    /// Removes the burner role from `burner`.
+    /// Revoking is not timelocked so a compromised burner can be stopped right away.
+    pub fn revoke_burner(&mut self, burner: Address) -> Result<(), Vec<u8>> {
+        self.ownable.only_role(MINTER_ADMIN)?;
+        self.set_burner(burner, false);
+
+        Ok(())
+    }
+
+    /// Whether `burner` can burn any token without an approval
+    pub fn is_burner(&self, burner: Address) -> Result<bool, Vec<u8>> {
+        Ok(self.burners.get(burner))
+    }
+
+    /// Returns every address currently allowed to mint
+    pub fn minters(&self) -> Result<Vec<Address>, Vec<u8>> {
+        let mut minters = Vec::with_capacity(self.minter_list.len());
+        for i in 0..self.minter_list.len() {
+            if let Some(minter) = self.minter_list.get(i) {
+                minters.push(minter);
+            }
+        }
+
+        Ok(minters)
+    }
+
+    /// Whether `minter` has any allowance left
+    pub fn is_minter(&self, minter: Address) -> Result<bool, Vec<u8>> {
+        Ok(!self.minter_positions.get(minter).is_zero())
+    }
+
+    /// Returns the amount of tokens that `minter` is still allowed to mint
+    pub fn minter_allowance(&self, minter: Address) -> Result<U256, Vec<u8>> {
+        Ok(self.minter_allowances.get(minter))
+    }
+
+    /// Burns `token_id`. The caller must be the owner or approved to spend it.
     pub fn burn(&mut self, token_id: U256) -> Result<(), Erc721Error> {
-        self.erc721.burn(msg::sender(), token_id)?;
+        let from = self.erc721.owner_of(token_id)?;
+        self.burn_internal(from, token_id)
+    }
+
+    /// Burns `token_id` on behalf of `from`. The caller must be approved to spend it, or a burner.
+    pub fn burn_from(&mut self, from: Address, token_id: U256) -> Result<(), Erc721Error> {
+        self.burn_internal(from, token_id)
+    }
+
+    /// Burns all the `token_ids`. It will fail the transaction if some burn fails.
+    pub fn burn_batch(&mut self, token_ids: Vec<U256>) -> Result<(), Erc721Error> {
+        for token_id in token_ids {
+            let from = self.erc721.owner_of(token_id)?;
+            self.burn_internal(from, token_id)?;
+        }
+
         Ok(())
     }
 
     pub fn mint(&mut self, to: Address) -> Result<U256, Vec<u8>> {
-        if !self.minters.get(msg::sender()) {
-            return Err(SeabrickError::OnlyMinters(OnlyMinters {}).into());
-        }
+        self.spend_minter_allowance(U256::from(1u8))?;
 
         self.erc721.mint(to)?;
         Ok(self.erc721.total_supply.get() - U256::from(1u8))
     }
 
-    pub fn mint_batch(&mut self, to: Address, amount: u8) -> Result<(), Vec<u8>> {
-        if !self.minters.get(msg::sender()) {
-            return Err(SeabrickError::OnlyMinters(OnlyMinters {}).into());
+    pub fn mint_batch(&mut self, to: Address, amount: u32) -> Result<(), Vec<u8>> {
+        self.spend_minter_allowance(U256::from(amount))?;
+
+        self.erc721.mint_batch(to, amount)?;
+
+        Ok(())
+    }
+
+    /// Mints `amounts[i]` tokens to each `recipients[i]` in a single transaction.
+    /// It will fail the whole transaction if any recipient is the zero address.
+    pub fn mint_to_many(
+        &mut self,
+        recipients: Vec<Address>,
+        amounts: Vec<u32>,
+    ) -> Result<(), Vec<u8>> {
+        if recipients.len() != amounts.len() {
+            return Err(SeabrickError::MismatchRecipients(MismatchRecipients {}).into());
         }
 
-        for _ in 0..amount.into() {
-            self.erc721.mint(to)?;
+        let total = amounts
+            .iter()
+            .fold(U256::ZERO, |total, amount| total + U256::from(*amount));
+        self.spend_minter_allowance(total)?;
+
+        for (to, amount) in recipients.into_iter().zip(amounts) {
+            self.erc721.mint_batch(to, amount)?;
         }
 
         Ok(())
     }
 }
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use crate::erc721::TransferToZero;
+    use alloc::vec;
+    use seabrick_common::{
+        erc165::{supportsInterfaceCall, ERC165_INTERFACE_ID},
+        ownable::{
+            hasRoleCall, InvalidOwnership, OwnableError, OwnableMissingRole,
+            OwnableUnauthorizedAccount, OwnershipContractChanged, OwnershipContractUnavailable,
+            OwnershipRenounced, ERC173_INTERFACE_ID,
+        },
+        testing,
+        timelock::{TimelockNotReady, TimelockNotScheduled},
+    };
+    use stylus_sdk::{
+        alloy_primitives::{address, B256},
+        alloy_sol_types::{SolError, SolEvent, SolValue},
+        storage::StorageType,
+    };
+
+    const ALICE: Address = address!("000000000000000000000000000000000000a11c");
+    const BOB: Address = address!("0000000000000000000000000000000000000b0b");
+    const CAROL: Address = address!("00000000000000000000000000000000000ca201");
+
+    /// A Seabrick where the test sender is a minter
+    fn seabrick() -> Seabrick {
+        testing::reset();
+        let mut seabrick = unsafe { Seabrick::new(U256::ZERO, 0) };
+        seabrick.set_minter_allowance(testing::SENDER, U256::from(100));
+        seabrick
+    }
+
+    /// Makes the ownership contract report `owner` as the owner
+    fn owned_by(owner: Address) {
+        ownership(owner, None);
+    }
+
+    /// Makes the ownership contract report `owner` as the owner and
+    /// `minter_admin` as the only account with the MINTER_ADMIN role
+    fn ownership(owner: Address, minter_admin: Option<Address>) {
+        testing::on_call(move |_, calldata| {
+            if calldata.starts_with(&hasRoleCall::SELECTOR) {
+                let call = hasRoleCall::abi_decode(calldata, true).unwrap();
+                let granted = call.role == MINTER_ADMIN && Some(call.account) == minter_admin;
+                return Ok(granted.abi_encode());
+            }
+            if calldata.starts_with(&supportsInterfaceCall::SELECTOR) {
+                return Ok(supports_erc173(calldata));
+            }
+            Ok(owner.abi_encode())
+        });
+    }
+
+    /// Answers a `supportsInterface` call as an ERC-173 contract
+    fn supports_erc173(calldata: &[u8]) -> Vec<u8> {
+        let call = supportsInterfaceCall::abi_decode(calldata, true).unwrap();
+        [ERC165_INTERFACE_ID, ERC173_INTERFACE_ID]
+            .contains(&call.interface_id)
+            .abi_encode()
+    }
+
+    /// Schedules the owner call `data` to be executable right away
+    fn schedule(seabrick: &mut Seabrick, data: Vec<u8>) {
+        let eta = U256::from(testing::TIMESTAMP);
+        assert!(seabrick.schedule(data.into(), eta).is_ok());
+    }
+
+    fn unavailable(ownership_contract: Address) -> Vec<u8> {
+        OwnableError::OwnershipContractUnavailable(OwnershipContractUnavailable {
+            ownership_contract,
+        })
+        .into()
+    }
+
+    fn owner(seabrick: &Seabrick, token_id: u32) -> Option<Address> {
+        seabrick.erc721.owner_of(U256::from(token_id)).ok()
+    }
+
+    /// Takes the `Burned` events emitted so far, as (from, operator, token id)
+    fn burned_events() -> Vec<(Address, Address, U256)> {
+        testing::take_logs()
+            .into_iter()
+            .filter(|log| log.topics[0] == Burned::SIGNATURE_HASH.0)
+            .map(|log| {
+                let topics = log.topics.into_iter().map(B256::from);
+                let event = Burned::decode_raw_log(topics, &log.data, true).unwrap();
+                (event.from, event.operator, event.token_id)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mint_to_many_mints_each_amount_to_its_recipient() {
+        let mut seabrick = seabrick();
+
+        assert!(seabrick.mint_to_many(vec![ALICE, BOB], vec![2, 3]).is_ok());
+
+        assert_eq!(seabrick.erc721.total_supply.get(), U256::from(5));
+        for token_id in 0..2 {
+            assert_eq!(owner(&seabrick, token_id), Some(ALICE));
+        }
+        for token_id in 2..5 {
+            assert_eq!(owner(&seabrick, token_id), Some(BOB));
+        }
+        assert_eq!(seabrick.erc721.balance_of(ALICE).ok(), Some(U256::from(2)));
+        assert_eq!(seabrick.erc721.balance_of(BOB).ok(), Some(U256::from(3)));
+    }
+
+    #[test]
+    fn mint_to_many_rejects_mismatched_lengths() {
+        let mut seabrick = seabrick();
+
+        assert_eq!(
+            seabrick.mint_to_many(vec![ALICE, BOB], vec![1]),
+            Err(SeabrickError::MismatchRecipients(MismatchRecipients {}).into())
+        );
+        assert_eq!(seabrick.erc721.total_supply.get(), U256::ZERO);
+    }
+
+    #[test]
+    fn mint_to_many_rejects_the_zero_address() {
+        let mut seabrick = seabrick();
+
+        assert_eq!(
+            seabrick.mint_to_many(vec![ALICE, Address::ZERO], vec![1, 1]),
+            Err(Erc721Error::TransferToZero(TransferToZero {
+                token_id: U256::from(1)
+            })
+            .into())
+        );
+    }
+
+    #[test]
+    fn mint_to_many_is_only_for_minters() {
+        let mut seabrick = seabrick();
+        seabrick.set_minter_allowance(testing::SENDER, U256::ZERO);
+
+        assert_eq!(
+            seabrick.mint_to_many(vec![ALICE], vec![1]),
+            Err(SeabrickError::OnlyMinters(OnlyMinters {}).into())
+        );
+    }
+
+    #[test]
+    fn minting_spends_the_allowance() {
+        let mut seabrick = seabrick();
+        seabrick.set_minter_allowance(testing::SENDER, U256::from(3));
+
+        assert!(seabrick.mint_batch(ALICE, 2).is_ok());
+        assert_eq!(
+            seabrick.minter_allowance(testing::SENDER),
+            Ok(U256::from(1))
+        );
+
+        assert_eq!(
+            seabrick.mint_batch(ALICE, 2),
+            Err(
+                SeabrickError::MinterAllowanceExceeded(MinterAllowanceExceeded {
+                    minter: testing::SENDER,
+                    allowance: U256::from(1),
+                    amount: U256::from(2),
+                })
+                .into()
+            )
+        );
+
+        assert_eq!(seabrick.mint(ALICE), Ok(U256::from(2)));
+        assert_eq!(seabrick.minter_allowance(testing::SENDER), Ok(U256::ZERO));
+        assert_eq!(
+            seabrick.mint(ALICE),
+            Err(SeabrickError::OnlyMinters(OnlyMinters {}).into())
+        );
+    }
+
+    #[test]
+    fn owner_manages_the_allowances() {
+        let mut seabrick = seabrick();
+        owned_by(testing::SENDER);
+        schedule(
+            &mut seabrick,
+            grantMinterCall {
+                minter: ALICE,
+                allowance: U256::from(5),
+            }
+            .abi_encode(),
+        );
+        schedule(
+            &mut seabrick,
+            increaseMinterAllowanceCall {
+                minter: ALICE,
+                amount: U256::from(3),
+            }
+            .abi_encode(),
+        );
+
+        assert!(seabrick.grant_minter(ALICE, U256::from(5)).is_ok());
+        assert_eq!(seabrick.minter_allowance(ALICE), Ok(U256::from(5)));
+
+        assert!(seabrick
+            .increase_minter_allowance(ALICE, U256::from(3))
+            .is_ok());
+        assert_eq!(seabrick.minter_allowance(ALICE), Ok(U256::from(8)));
+
+        assert!(seabrick.revoke_minter(ALICE).is_ok());
+        assert_eq!(seabrick.minter_allowance(ALICE), Ok(U256::ZERO));
+
+        // Every change reports the remaining quota
+        let allowances: Vec<U256> = testing::take_logs()
+            .iter()
+            .filter(|log| log.topics[0] == MinterUpdated::SIGNATURE_HASH.0)
+            .skip(1)
+            .map(|log| MinterUpdated::abi_decode_data(&log.data, true).unwrap().1)
+            .collect();
+        assert_eq!(allowances, [U256::from(5), U256::from(8), U256::ZERO]);
+    }
+
+    #[test]
+    fn minter_admins_manage_the_allowances() {
+        let mut seabrick = seabrick();
+        ownership(BOB, Some(testing::SENDER));
+        schedule(
+            &mut seabrick,
+            grantMinterCall {
+                minter: ALICE,
+                allowance: U256::from(5),
+            }
+            .abi_encode(),
+        );
+
+        assert!(seabrick.grant_minter(ALICE, U256::from(5)).is_ok());
+        assert!(seabrick.revoke_minter(ALICE).is_ok());
+        assert_eq!(seabrick.minter_allowance(ALICE), Ok(U256::ZERO));
+
+        // The role doesn't give access to the other owner calls
+        let data = setMinDelayCall { delay: U256::ZERO }.abi_encode();
+        assert!(matches!(
+            seabrick.schedule(data.into(), U256::from(testing::TIMESTAMP)),
+            Err(err) if err.starts_with(&OwnableUnauthorizedAccount::SELECTOR)
+        ));
+    }
+
+    #[test]
+    fn only_minter_admins_manage_the_allowances() {
+        let mut seabrick = seabrick();
+        ownership(BOB, Some(CAROL));
+
+        let unauthorized: Result<(), Vec<u8>> =
+            Err(OwnableError::MissingRole(OwnableMissingRole {
+                account: testing::SENDER,
+                role: MINTER_ADMIN,
+            })
+            .into());
+        let data = grantMinterCall {
+            minter: ALICE,
+            allowance: U256::from(5),
+        }
+        .abi_encode();
+        assert_eq!(
+            seabrick
+                .schedule(data.into(), U256::from(testing::TIMESTAMP))
+                .map(|_| ()),
+            unauthorized
+        );
+        assert_eq!(seabrick.grant_minter(ALICE, U256::from(5)), unauthorized);
+        assert_eq!(
+            seabrick.increase_minter_allowance(ALICE, U256::from(5)),
+            unauthorized
+        );
+        assert_eq!(seabrick.revoke_minter(testing::SENDER), unauthorized);
+        assert_eq!(seabrick.minter_allowance(ALICE), Ok(U256::ZERO));
+    }
+
+    #[test]
+    fn nothing_is_managed_after_the_ownership_is_renounced() {
+        let mut seabrick = seabrick();
+        ownership(Address::ZERO, Some(testing::SENDER));
+
+        let renounced: Result<(), Vec<u8>> =
+            Err(OwnableError::OwnershipRenounced(OwnershipRenounced {}).into());
+        assert_eq!(seabrick.revoke_minter(testing::SENDER), renounced);
+        assert_eq!(
+            seabrick.cancel(setMinDelayCall { delay: U256::ZERO }.abi_encode().into()),
+            renounced
+        );
+        assert_eq!(seabrick.is_minter(testing::SENDER), Ok(true));
+    }
+
+    #[test]
+    fn ownership_contract_acts_as_the_owner() {
+        let mut seabrick = seabrick();
+        ownership(BOB, None);
+        // The ownership contract calls the NFT, eg. in a batch
+        seabrick.ownable._ownership.set(testing::SENDER);
+
+        let data = setMinDelayCall { delay: U256::ZERO }.abi_encode();
+        assert!(seabrick
+            .schedule(data.into(), U256::from(testing::TIMESTAMP))
+            .is_ok());
+        assert!(seabrick.revoke_minter(testing::SENDER).is_ok());
+        assert_eq!(seabrick.is_minter(testing::SENDER), Ok(false));
+    }
+
+    #[test]
+    fn ownership_contract_must_report_erc173() {
+        let mut seabrick = seabrick();
+        owned_by(testing::SENDER);
+
+        assert!(seabrick.ownable.set_ownership_contract(ALICE).is_ok());
+        assert_eq!(seabrick.ownable.ownership_contract(), ALICE);
+        let log = testing::take_logs().pop().unwrap();
+        assert_eq!(log.topics[0], OwnershipContractChanged::SIGNATURE_HASH.0);
+
+        // Any other answer, or a revert, is rejected. So is a contract that claims
+        // every interface, since it also claims the invalid id 0xffffffff.
+        for answer in [
+            Ok(false.abi_encode()),
+            Ok(true.abi_encode()),
+            Ok(vec![]),
+            Err(vec![]),
+        ] {
+            testing::on_call(move |_, _| answer.clone());
+            assert_eq!(
+                seabrick.ownable.set_ownership_contract(BOB),
+                Err(unavailable(BOB))
+            );
+        }
+        assert_eq!(seabrick.ownable.ownership_contract(), ALICE);
+    }
+
+    #[test]
+    fn ownership_contract_must_be_a_contract() {
+        let mut seabrick = seabrick();
+
+        // Without a call handler no account has code
+        assert_eq!(
+            seabrick.ownable.set_ownership_contract(ALICE),
+            Err(unavailable(ALICE))
+        );
+    }
+
+    #[test]
+    fn unreachable_ownership_contract_fails_with_a_typed_error() {
+        let mut seabrick = seabrick();
+        seabrick.ownable._ownership.set(ALICE);
+        testing::on_call(|_, _| Err(vec![]));
+
+        assert_eq!(seabrick.ownable.owner(), Err(unavailable(ALICE)));
+        assert_eq!(
+            seabrick.revoke_minter(testing::SENDER),
+            Err(unavailable(ALICE))
+        );
+    }
+
+    #[test]
+    fn ownership_contract_changes_keep_the_owner() {
+        let mut seabrick = seabrick();
+        seabrick.ownable._ownership.set(ALICE);
+        testing::on_call(|to, calldata| {
+            if calldata.starts_with(&supportsInterfaceCall::SELECTOR) {
+                return Ok(supports_erc173(calldata));
+            }
+            // Bob's contract reports another owner
+            Ok(match to {
+                BOB => CAROL.abi_encode(),
+                _ => testing::SENDER.abi_encode(),
+            })
+        });
+
+        assert!(matches!(
+            seabrick.ownable.change_ownership_contract(BOB),
+            Err(err) if err.starts_with(&InvalidOwnership::SELECTOR)
+        ));
+        assert!(seabrick.ownable.change_ownership_contract(CAROL).is_ok());
+        assert_eq!(seabrick.ownable.ownership_contract(), CAROL);
+    }
+
+    #[test]
+    fn owner_is_read_once_per_call() {
+        let mut seabrick = seabrick();
+        seabrick.ownable._ownership.set(ALICE);
+        ownership(testing::SENDER, None);
+
+        assert!(seabrick.ownable.only_owner().is_ok());
+        assert!(seabrick.ownable.only_role(MINTER_ADMIN).is_ok());
+        assert_eq!(seabrick.ownable.owner(), Ok(testing::SENDER));
+        assert_eq!(testing::take_calls(), [(ALICE, U256::ZERO)]);
+
+        // The next call reads it again, and sees the ownership transfer
+        ownership(BOB, None);
+        let next_call = unsafe { Seabrick::new(U256::ZERO, 0) };
+        assert!(next_call.ownable.only_owner().is_err());
+    }
+
+    #[test]
+    fn ownership_contract_changes_clear_the_cached_owner() {
+        let mut seabrick = seabrick();
+        seabrick.ownable._ownership.set(ALICE);
+        testing::on_call(|to, calldata| {
+            if calldata.starts_with(&supportsInterfaceCall::SELECTOR) {
+                return Ok(supports_erc173(calldata));
+            }
+            Ok(match to {
+                BOB => CAROL.abi_encode(),
+                _ => testing::SENDER.abi_encode(),
+            })
+        });
+        assert_eq!(seabrick.ownable.owner(), Ok(testing::SENDER));
+
+        assert!(seabrick.ownable.set_ownership_contract(BOB).is_ok());
+
+        assert_eq!(seabrick.ownable.owner(), Ok(CAROL));
+    }
+
+    #[test]
+    fn allowance_changes_wait_for_the_timelock() {
+        let mut seabrick = seabrick();
+        owned_by(testing::SENDER);
+        seabrick.timelock._set_min_delay(U256::from(60));
+
+        // Not scheduled
+        assert!(matches!(
+            seabrick.grant_minter(ALICE, U256::from(5)),
+            Err(err) if err.starts_with(&TimelockNotScheduled::SELECTOR)
+        ));
+
+        // Scheduled, but not ready yet
+        let eta = U256::from(testing::TIMESTAMP + 60);
+        let data = grantMinterCall {
+            minter: ALICE,
+            allowance: U256::from(5),
+        }
+        .abi_encode();
+        assert!(seabrick.schedule(data.into(), eta).is_ok());
+        assert!(matches!(
+            seabrick.grant_minter(ALICE, U256::from(5)),
+            Err(err) if err.starts_with(&TimelockNotReady::SELECTOR)
+        ));
+        assert_eq!(seabrick.minter_allowance(ALICE), Ok(U256::ZERO));
+
+        // Revoking is instant
+        assert!(seabrick.revoke_minter(testing::SENDER).is_ok());
+        assert_eq!(seabrick.is_minter(testing::SENDER), Ok(false));
+    }
+
+    #[test]
+    fn minter_set_follows_the_allowances() {
+        let mut seabrick = seabrick();
+        for minter in [ALICE, BOB, CAROL] {
+            seabrick.set_minter_allowance(minter, U256::from(1));
+        }
+        assert_eq!(
+            seabrick.minters(),
+            Ok(vec![testing::SENDER, ALICE, BOB, CAROL])
+        );
+
+        // Removing from the middle moves the last minter into the gap
+        seabrick.set_minter_allowance(ALICE, U256::ZERO);
+        assert_eq!(seabrick.minters(), Ok(vec![testing::SENDER, CAROL, BOB]));
+        assert_eq!(seabrick.is_minter(ALICE), Ok(false));
+        assert_eq!(seabrick.is_minter(CAROL), Ok(true));
+
+        // A new allowance for a current minter doesn't list it twice
+        seabrick.set_minter_allowance(CAROL, U256::from(2));
+        assert_eq!(seabrick.minters(), Ok(vec![testing::SENDER, CAROL, BOB]));
+
+        seabrick.set_minter_allowance(BOB, U256::ZERO);
+        seabrick.set_minter_allowance(CAROL, U256::ZERO);
+        assert_eq!(seabrick.minters(), Ok(vec![testing::SENDER]));
+        assert_eq!(seabrick.is_minter(BOB), Ok(false));
+    }
+
+    #[test]
+    fn spending_the_whole_allowance_leaves_the_minter_set() {
+        let mut seabrick = seabrick();
+        seabrick.set_minter_allowance(testing::SENDER, U256::from(2));
+
+        assert!(seabrick.mint_batch(ALICE, 2).is_ok());
+
+        assert_eq!(seabrick.minters(), Ok(vec![]));
+        assert_eq!(seabrick.is_minter(testing::SENDER), Ok(false));
+    }
+
+    #[test]
+    fn holders_burn_their_tokens() {
+        let mut seabrick = seabrick();
+        assert!(seabrick.mint_batch(testing::SENDER, 2).is_ok());
+
+        assert!(seabrick.burn(U256::ZERO).is_ok());
+
+        assert_eq!(owner(&seabrick, 0), None);
+        assert_eq!(owner(&seabrick, 1), Some(testing::SENDER));
+        assert_eq!(
+            burned_events(),
+            [(testing::SENDER, testing::SENDER, U256::ZERO)]
+        );
+    }
+
+    #[test]
+    fn operators_burn_on_behalf_of_the_holder() {
+        let mut seabrick = seabrick();
+        assert!(seabrick.mint_batch(ALICE, 2).is_ok());
+        assert!(seabrick.mint_batch(BOB, 1).is_ok());
+
+        // Operator for every token of Alice
+        seabrick
+            .erc721
+            .operator_approvals
+            .setter(ALICE)
+            .insert(testing::SENDER, true);
+        assert!(seabrick.burn_from(ALICE, U256::from(1)).is_ok());
+
+        // Approved for a single token of Bob
+        seabrick
+            .erc721
+            .token_approvals
+            .insert(U256::from(2), testing::SENDER);
+        assert!(seabrick.burn(U256::from(2)).is_ok());
+
+        assert_eq!(owner(&seabrick, 0), Some(ALICE));
+        assert_eq!(owner(&seabrick, 1), None);
+        assert_eq!(owner(&seabrick, 2), None);
+        assert_eq!(
+            burned_events(),
+            [
+                (ALICE, testing::SENDER, U256::from(1)),
+                (BOB, testing::SENDER, U256::from(2))
+            ]
+        );
+    }
+
+    #[test]
+    fn burns_need_the_holder_approval() {
+        let mut seabrick = seabrick();
+        assert!(seabrick.mint_batch(ALICE, 1).is_ok());
+
+        assert!(matches!(
+            seabrick.burn(U256::ZERO),
+            Err(Erc721Error::NotApproved(_))
+        ));
+        assert!(matches!(
+            seabrick.burn_from(BOB, U256::ZERO),
+            Err(Erc721Error::NotOwner(_))
+        ));
+        assert_eq!(owner(&seabrick, 0), Some(ALICE));
+        assert_eq!(burned_events(), []);
+    }
+
+    #[test]
+    fn burn_batch_burns_every_token() {
+        let mut seabrick = seabrick();
+        assert!(seabrick.mint_batch(testing::SENDER, 3).is_ok());
+        assert!(seabrick.mint_batch(ALICE, 1).is_ok());
+
+        assert!(seabrick.burn_batch(vec![U256::ZERO, U256::from(2)]).is_ok());
+
+        assert_eq!(owner(&seabrick, 0), None);
+        assert_eq!(owner(&seabrick, 1), Some(testing::SENDER));
+        assert_eq!(owner(&seabrick, 2), None);
+        assert_eq!(
+            seabrick.erc721.balance_of(testing::SENDER).ok(),
+            Some(U256::from(1))
+        );
+        assert_eq!(burned_events().len(), 2);
+
+        // A token the caller can't spend fails the whole batch
+        assert!(matches!(
+            seabrick.burn_batch(vec![U256::from(1), U256::from(3)]),
+            Err(Erc721Error::NotApproved(_))
+        ));
+    }
+
+    #[test]
+    fn burners_burn_without_an_approval() {
+        let mut seabrick = seabrick();
+        owned_by(testing::SENDER);
+        assert!(seabrick.mint_batch(ALICE, 2).is_ok());
+
+        // Granting waits for the timelock
+        assert!(seabrick.grant_burner(testing::SENDER).is_err());
+        schedule(
+            &mut seabrick,
+            grantBurnerCall {
+                burner: testing::SENDER,
+            }
+            .abi_encode(),
+        );
+        assert!(seabrick.grant_burner(testing::SENDER).is_ok());
+        assert_eq!(seabrick.is_burner(testing::SENDER), Ok(true));
+
+        assert!(seabrick.burn_from(ALICE, U256::ZERO).is_ok());
+        assert_eq!(owner(&seabrick, 0), None);
+        assert_eq!(burned_events(), [(ALICE, testing::SENDER, U256::ZERO)]);
+
+        // The holder must still match
+        assert!(matches!(
+            seabrick.burn_from(BOB, U256::from(1)),
+            Err(Erc721Error::NotOwner(_))
+        ));
+
+        // Revoking is immediate
+        assert!(seabrick.revoke_burner(testing::SENDER).is_ok());
+        assert_eq!(seabrick.is_burner(testing::SENDER), Ok(false));
+        assert!(matches!(
+            seabrick.burn_from(ALICE, U256::from(1)),
+            Err(Erc721Error::NotApproved(_))
+        ));
+    }
+}